@@ -1,10 +1,14 @@
-use crate::stack;
-use std::collections::HashMap;
+use crate::builtins;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+use std::sync::LazyLock;
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct AST {
     pub node: ASTnode,
-    code: String,
+    pub code: Rc<str>, //shared with what is compiled from it, so its text can be sliced out when it is needed
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -15,6 +19,25 @@ pub struct ASTnode {
     pub number: Option<f64>,
     pub function: Option<char>, //named refrence for lazy evaluation and, therefore, recursion
     pub children: Option<Vec<ASTnode>>,
+    pub location: Location, //where the node starts in its file, for runtime errors
+    pub span: Range<usize>, //the bytes of its AST's code it came from, brackets and all
+}
+
+//where something is in a file. lines and columns start at 1 and columns count chars
+#[derive(Clone, PartialEq, Debug)]
+pub struct Location {
+    pub file: Rc<str>,
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub at: Location,
+    pub opened_at: Option<Location>, //for brackets, where the bracket that went wrong was opened
+    source_line: Option<Box<str>>,
+    opened_source_line: Option<Box<str>>,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -57,12 +80,23 @@ pub enum Commands {
     NilCommand,
     IsNilCommand,
     MatchCommand,
+    ExecuteCommand,
+    GetXCommand,
+    GetYCommand,
+    SetXCommand,
+    SetYCommand,
+    GetHeadingCommand,
+    SetHeadingCommand,
+    BeginFillCommand,
+    EndFillCommand,
+    EndEvenOddFillCommand,
 }
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum ControlStructures {
     RepeatLoop,
     WhileLoop,
+    Quotation, //not really control flow, but it is code in brackets like the loops
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -74,271 +108,437 @@ pub enum ASTnodeType {
     Function,
 }
 
-const ALLOWED_CHARS: [char; 53] = [
-    '^', '~', '.', ':', '!', '+', '-', '*', '/', '%', ' ', '0', '1', '2', '3', '4', '5', '6', '7',
-    '8', '9', '[', ']', '{', '}', '(', ')', 'o', 'r', 'c', 'd', 'u', 's', '?', 'P', 'l', 'e', 'q',
-    'S', 'C', 'f', 'R', '>', '<', '=', 'B', 'U', 'I', '@', '&', ',', ';', '|',
-];
+//the glyph of every command, built once from the builtins table the first time it is needed
+static COMMANDS: LazyLock<HashMap<char, Commands>> = LazyLock::new(|| {
+    builtins::BUILTINS
+        .iter()
+        .map(|builtin| (builtin.glyph, builtin.command.clone()))
+        .collect()
+});
 
-const CONVERSION_MAP: [(char, Commands); 38] = [
-    ('^', Commands::ForwardCommand),
-    ('~', Commands::TurnCommand),
-    ('.', Commands::DuplicateCommand),
-    (':', Commands::SwapCommand),
-    ('!', Commands::PopCommand),
-    ('+', Commands::AddCommand),
-    ('-', Commands::SubtractCommand),
-    ('*', Commands::MultiplyCommand),
-    ('/', Commands::DivideCommand),
-    ('%', Commands::ModuloCommand),
-    ('o', Commands::DegreeCommand),
-    ('r', Commands::RadianCommand),
-    ('c', Commands::ColorCommand),
-    ('d', Commands::PenDownCommand),
-    ('u', Commands::PenUpCommand),
-    ('s', Commands::SizeCommand),
-    ('?', Commands::DebugCommand),
-    ('P', Commands::PowerCommand),
-    ('l', Commands::LogCommand),
-    ('e', Commands::EulerNumCommand),
-    ('q', Commands::SquareRootCommand),
-    ('S', Commands::SineCommand),
-    ('C', Commands::CeilingCommand),
-    ('f', Commands::FloorCommand),
-    ('R', Commands::RoundCommand),
-    ('>', Commands::LessThanCommand),
-    ('<', Commands::GreaterThanCommand),
-    ('=', Commands::EqualCommand),
-    ('(', Commands::DipCommand),
-    (')', Commands::UndipCommand),
-    ('B', Commands::BoxCommand),
-    ('U', Commands::UnboxCommand),
-    ('I', Commands::IsBoxCommand),
-    ('@', Commands::RotCommand),
-    ('&', Commands::UnrotCommand),
-    (',', Commands::NilCommand),
-    (';', Commands::IsNilCommand),
-    ('|', Commands::MatchCommand),
-]; //just use HashMap::from when actually needed
+fn is_opening(c: char) -> bool {
+    builtins::BRACKETS.iter().any(|bracket| bracket.open == c)
+}
 
-const BRACK_CONV_MAP: [(char, ControlStructures); 2] = [
-    ('[', ControlStructures::RepeatLoop),
-    ('{', ControlStructures::WhileLoop),
-]; //ditto
+fn is_closing(c: char) -> bool {
+    builtins::BRACKETS.iter().any(|bracket| bracket.close == c)
+}
 
-const ALLOWED_COMMANDS: [char; 38] = [
-    '^', '~', '.', ':', '!', '+', '-', '*', '/', '%', 'o', 'r', 'c', 'd', 'u', 's', '?', 'P', 'l',
-    'e', 'q', 'S', 'C', 'f', 'R', '>', '<', '=', '(', ')', 'B', 'U', 'I', '@', '&', ',', ';', '|',
-];
+const NEGATIVE_SIGN: char = '¯'; //`-` is already subtract
 
-const ALLOWED_BRACKETS: [char; 4] = ['[', ']', '{', '}'];
+//how many chars long the number literal starting at idx is
+//`.` and `e` are also commands, so they only count as part of a number when there are digits on both sides
+fn number_length(chars: &[char], idx: usize) -> usize {
+    let is_digit = |at: usize| chars.get(at).is_some_and(|c| c.is_ascii_digit());
+    let is = |at: usize, want: char| chars.get(at) == Some(&want);
+    let digits_from = |mut at: usize| {
+        while is_digit(at) {
+            at += 1;
+        }
+        at
+    };
+    let mut end = idx;
+    if is(end, NEGATIVE_SIGN) {
+        end += 1;
+    }
+    end = digits_from(end);
+    if is(end, '.') && is_digit(end + 1) {
+        end = digits_from(end + 1);
+    }
+    if is(end, 'e') {
+        if is_digit(end + 1) {
+            end = digits_from(end + 1);
+        } else if is(end + 1, NEGATIVE_SIGN) && is_digit(end + 2) {
+            end = digits_from(end + 2);
+        }
+    }
+    end - idx
+}
 
-const NUMBER_CHARS: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+impl Location {
+    pub fn new(file: Rc<str>, line: usize, col: usize) -> Self {
+        Location { file, line, col }
+    }
+    pub fn start_of(file: &str) -> Self {
+        Location::new(Rc::from(file), 1, 1)
+    }
+    //moves past one char of code
+    fn advance(&mut self, c: char) {
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+    }
+}
 
-fn verify(code_in: String, functions: Vec<char>) -> Result<Vec<char>, String> {
-    let mut out = Vec::new();
-    let mut appended = false;
-    let mut bracket_check_stack: stack::Stack<char> = stack::Stack::new();
-    let mut peek_bracket: char;
-    for i in code_in.chars() {
-        for j in ALLOWED_CHARS {
-            if i == j {
-                out.push(i);
-                appended = true;
-                if i == '{' || i == '[' {
-                    bracket_check_stack.push(i);
-                }
-                peek_bracket = match bracket_check_stack.peek() {
-                    Some(n) => *n,
-                    None => ' ',
-                };
-                if (i == '}' && peek_bracket == '{') || (i == ']' && peek_bracket == '[') {
-                    let _throwaway = bracket_check_stack.pop();
-                    break;
-                }
-                if i == ']' || i == '}' {
-                    return Err("Mismached brackets".to_string());
-                }
-                break;
-            }
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.col)
+    }
+}
+
+impl ParseError {
+    pub fn new(message: String, at: Location, opened_at: Option<Location>) -> Self {
+        ParseError {
+            message,
+            at,
+            opened_at,
+            source_line: None,
+            opened_source_line: None,
+        }
+    }
+    //fills in the lines to quote from the text of the file the error is in
+    pub fn with_source(mut self, text: &str) -> Self {
+        self.source_line = text.lines().nth(self.at.line - 1).map(Box::from);
+        if let Some(opened_at) = &self.opened_at {
+            self.opened_source_line = text.lines().nth(opened_at.line - 1).map(Box::from);
         }
-        for j in &functions {
-            if i == *j {
-                out.push(i);
-                appended = true;
-                break;
+        self
+    }
+}
+
+pub fn write_snippet(
+    f: &mut fmt::Formatter<'_>,
+    at: &Location,
+    line: Option<&str>,
+    label: &str,
+) -> fmt::Result {
+    let Some(line) = line else {
+        return Ok(());
+    };
+    let gutter = at.line.to_string().len();
+    writeln!(f, "{:gutter$} |", "")?;
+    writeln!(f, "{} | {}", at.line, line)?;
+    //pad with the same chars as the line so tabs and wide chars still line up
+    let padding: String = line
+        .chars()
+        .take(at.col - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    write!(f, "{:gutter$} | {}^", "", padding)?;
+    if !label.is_empty() {
+        write!(f, " {}", label)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error: {}", self.message)?;
+        write!(f, " --> {}", self.at)?;
+        if self.source_line.is_some() {
+            writeln!(f)?;
+            let label = if self.opened_at.is_some() {
+                "closed here"
+            } else {
+                ""
+            };
+            write_snippet(f, &self.at, self.source_line.as_deref(), label)?;
+        }
+        if let Some(opened_at) = &self.opened_at {
+            write!(f, "\n --> {}", opened_at)?;
+            if self.opened_source_line.is_some() {
+                writeln!(f)?;
+                write_snippet(f, opened_at, self.opened_source_line.as_deref(), "opened here")?;
             }
         }
-        if !appended {
-            return Err(format!("{i} is not a valid command or named function").to_string());
+        Ok(())
+    }
+}
+
+//a stretch of code: where it starts in its file, and which bytes of the code it is
+#[derive(Clone, PartialEq, Debug)]
+pub struct Span {
+    pub start: Location,
+    pub bytes: Range<usize>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TokenKind {
+    Number(f64),
+    Command(Commands),
+    Function(char),
+    Open(char),  //an opening bracket
+    Close(char), //a closing bracket
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+//splits code into tokens as they are asked for, skipping whitespace. every char is looked at once
+//(numbers peek a couple ahead), and looking one up is a hash instead of a search through every command
+pub struct Lexer {
+    chars: Vec<char>,
+    idx: usize,
+    byte: usize, //idx, counted in bytes
+    pos: Location,
+    functions: HashSet<char>,
+}
+
+impl Lexer {
+    pub fn new(code_in: &str, functions: &[char], start: Location) -> Self {
+        Lexer {
+            chars: code_in.chars().collect(),
+            idx: 0,
+            byte: 0,
+            pos: start,
+            functions: functions.iter().copied().collect(),
         }
-        appended = false;
     }
-    if !bracket_check_stack.is_empty() {
-        return Err("Mismached brackets".to_string());
+    fn advance(&mut self, len: usize) {
+        for c in &self.chars[self.idx..self.idx + len] {
+            self.pos.advance(*c);
+            self.byte += c.len_utf8();
+        }
+        self.idx += len;
+    }
+    //nothing comes after an error
+    fn error(&mut self, message: String) -> Option<Result<Token, ParseError>> {
+        let at = self.pos.clone();
+        self.idx = self.chars.len();
+        Some(Err(ParseError::new(message, at, None)))
     }
-    return Ok(out);
 }
-impl AST {
-    pub fn new(code_in: String, functions: Vec<char>) -> Result<Self, String> {
-        let code_verified: Result<Vec<char>, String> = verify(code_in.clone(), functions.clone());
-        if !code_verified.is_ok() {
-            return Err(code_verified.unwrap_err()); //propagate the error to the repl
+
+impl Iterator for Lexer {
+    type Item = Result<Token, ParseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut c = *self.chars.get(self.idx)?;
+        while c == ' ' || c == '\n' || c == '\t' {
+            self.advance(1);
+            c = *self.chars.get(self.idx)?;
         }
-        let code_unwrapped = code_verified.unwrap();
-        let mut head_node = ASTnode {
-            nodetype: ASTnodeType::Container,
-            command: None,
-            structure: None,
-            number: None,
-            function: None,
-            children: Some(Vec::new()),
+        let (kind, len) = if let Some(command) = COMMANDS.get(&c) {
+            (TokenKind::Command(command.clone()), 1)
+        } else if is_opening(c) {
+            (TokenKind::Open(c), 1)
+        } else if is_closing(c) {
+            (TokenKind::Close(c), 1)
+        } else if c == NEGATIVE_SIGN || c.is_ascii_digit() {
+            if c == NEGATIVE_SIGN && !self.chars.get(self.idx + 1).is_some_and(|c| c.is_ascii_digit()) {
+                return self.error(format!("{NEGATIVE_SIGN} has to be followed by the digits of a number"));
+            }
+            let len = number_length(&self.chars, self.idx);
+            let literal: String = self.chars[self.idx..self.idx + len]
+                .iter()
+                .map(|c| if *c == NEGATIVE_SIGN { '-' } else { *c })
+                .collect();
+            (TokenKind::Number(literal.parse::<f64>().unwrap()), len) //number_length only allows valid floats
+        } else if self.functions.contains(&c) {
+            (TokenKind::Function(c), 1)
+        } else {
+            return self.error(format!("{c} is not a valid command or named function"));
         };
-        head_node.populate_children(code_unwrapped, functions);
-        let new_ast = AST {
-            code: code_in,
-            node: head_node,
+        let (start, first) = (self.pos.clone(), self.byte);
+        self.advance(len);
+        let span = Span {
+            start,
+            bytes: first..self.byte,
         };
-        return Ok(new_ast);
+        Some(Ok(Token { kind, span }))
     }
 }
-impl ASTnode {
-    pub fn populate_children(&mut self, code_in: Vec<char>, functions: Vec<char>) {
-        let conversion_map: HashMap<char, Commands> = HashMap::from(CONVERSION_MAP);
-        let brack_conv_map: HashMap<char, ControlStructures> = HashMap::from(BRACK_CONV_MAP);
-        let mut idx = 0;
-        let mut token: char;
-        'token_loop: while idx < code_in.len() {
-            token = code_in[idx];
-            for com in ALLOWED_COMMANDS {
-                if token == com {
-                    //dealing with a command
-                    self.children.as_mut().unwrap().push(ASTnode {
-                        nodetype: ASTnodeType::Command,
-                        command: Some(conversion_map.get(&token).unwrap().clone()),
-                        structure: None,
-                        number: None,
-                        function: None,
-                        children: None,
-                    });
-                    idx += 1;
-                    continue 'token_loop;
-                }
+
+//builds the tree in one pass over the tokens. a bracket's node waits on a stack until it is closed
+//instead of its insides being copied out and parsed again, so nesting doesn't make it any slower
+fn parse(code_in: &str, functions: &[char], start: Location) -> Result<ASTnode, ParseError> {
+    let mut current = ASTnode::new(ASTnodeType::Container, start.clone(), 0..code_in.len());
+    current.children = Some(Vec::new());
+    let mut open: Vec<(char, ASTnode)> = Vec::new(); //each bracket waiting to be closed, and the node it is in
+    for token in Lexer::new(code_in, functions, start) {
+        let Token { kind, span } = token?;
+        let (location, bytes) = (span.start, span.bytes);
+        let node = match kind {
+            TokenKind::Open(bracket) => {
+                let mut node = ASTnode::new(ASTnodeType::ControlStructure, location, bytes);
+                node.structure = builtins::find_bracket(bracket).map(|found| found.structure.clone());
+                node.children = Some(Vec::new());
+                open.push((bracket, std::mem::replace(&mut current, node)));
+                continue;
             }
-            for brack in ALLOWED_BRACKETS {
-                if token == brack {
-                    //dealing with a bracket
-                    idx += 1;
-                    let mut code_to_push: Vec<char> = Vec::new();
-                    let mut bracket_depth: i64 = 1;
-                    let mut current_pos: char;
-                    while idx < code_in.len() {
-                        current_pos = code_in[idx];
-                        if bracket_depth == 0 {
-                            break;
-                        }
-                        bracket_depth += if current_pos == ']' || current_pos == '}' {
-                            -1
-                        } else if current_pos == '{' || current_pos == '[' {
-                            1
-                        } else {
-                            0
-                        };
-                        code_to_push.push(current_pos);
-                        idx += 1;
-                    }
-                    let _throwaway = code_to_push.pop();
-                    let mut new_ast_node: ASTnode = ASTnode {
-                        nodetype: ASTnodeType::ControlStructure,
-                        command: None,
-                        structure: Some(brack_conv_map.get(&token).unwrap().clone()),
-                        number: None,
-                        function: None,
-                        children: Some(Vec::new()),
-                    };
-                    new_ast_node.populate_children(code_to_push, functions.clone());
-                    self.children.as_mut().unwrap().push(new_ast_node);
-                    continue 'token_loop;
+            TokenKind::Close(bracket) => {
+                let Some((opening, parent)) = open.pop() else {
+                    return Err(ParseError::new(
+                        format!("Mismatched brackets: `{bracket}` has no opening bracket"),
+                        location,
+                        None,
+                    ));
+                };
+                if builtins::find_bracket(opening).unwrap().close != bracket {
+                    return Err(ParseError::new(
+                        format!("Mismatched brackets: `{opening}` is closed by `{bracket}`"),
+                        location,
+                        Some(current.location),
+                    ));
                 }
+                current.span.end = bytes.end;
+                std::mem::replace(&mut current, parent)
             }
-            for num in NUMBER_CHARS {
-                if token == num {
-                    //dealing with a number
-                    let mut topush: f64 = token.clone().to_string().parse::<f64>().unwrap();
-                    idx += 1;
-                    'numloop: while idx < code_in.len() {
-                        //while loop for bounds checking(blocks should be able to end with numbers)
-                        token = code_in[idx];
-                        for num2 in NUMBER_CHARS {
-                            if token == num2 {
-                                topush *= 10.0;
-                                topush += token.clone().to_string().parse::<f64>().unwrap();
-                                idx += 1;
-                                continue 'numloop;
-                            }
-                        }
-                        break;
-                    }
-                    self.children.as_mut().unwrap().push(ASTnode {
-                        nodetype: ASTnodeType::Number,
-                        command: None,
-                        structure: None,
-                        number: Some(topush),
-                        function: None,
-                        children: None,
-                    });
-                    continue 'token_loop;
-                }
+            TokenKind::Number(number) => {
+                let mut node = ASTnode::new(ASTnodeType::Number, location, bytes);
+                node.number = Some(number);
+                node
             }
-            for fun in &functions {
-                if token == *fun {
-                    //dealing with a function
-                    self.children.as_mut().unwrap().push(ASTnode {
-                        nodetype: ASTnodeType::Function,
-                        command: None,
-                        structure: None,
-                        number: None,
-                        function: Some(token),
-                        children: None,
-                    });
-                    idx += 1;
-                    continue 'token_loop;
-                }
+            TokenKind::Command(command) => {
+                let mut node = ASTnode::new(ASTnodeType::Command, location, bytes);
+                node.command = Some(command);
+                node
             }
-            if token == ' ' {
-                idx += 1;
+            TokenKind::Function(name) => {
+                let mut node = ASTnode::new(ASTnodeType::Function, location, bytes);
+                node.function = Some(name);
+                node
             }
+        };
+        current.children.as_mut().unwrap().push(node);
+    }
+    if let Some((opening, _)) = open.pop() {
+        return Err(ParseError::new(
+            format!("Mismatched brackets: `{opening}` is never closed"),
+            current.location,
+            None,
+        ));
+    }
+    Ok(current)
+}
+
+impl AST {
+    #[cfg(test)]
+    pub fn new(code_in: String, functions: Vec<char>) -> Result<Self, ParseError> {
+        Self::new_at(code_in, functions, Location::start_of("<input>"))
+    }
+    //like new, but errors are reported relative to where the code starts in its file
+    pub fn new_at(
+        code_in: String,
+        functions: Vec<char>,
+        start: Location,
+    ) -> Result<Self, ParseError> {
+        let node = parse(&code_in, &functions, start)?;
+        Ok(AST {
+            code: Rc::from(code_in),
+            node,
+        })
+    }
+}
+impl ASTnode {
+    //a node with nothing filled in besides what kind it is and where
+    fn new(nodetype: ASTnodeType, location: Location, span: Range<usize>) -> Self {
+        ASTnode {
+            nodetype,
+            command: None,
+            structure: None,
+            number: None,
+            function: None,
+            children: None,
+            location,
+            span,
         }
     }
 }
 
 #[test]
-fn verify_test() {
-    let should_work_tokens: Result<Vec<char>, String> = verify("4[5^90~]".to_string(), Vec::new());
-    println!("testing 4[5^90~]");
+fn bracket_matching_test() {
+    let parse_code = |code: &str| parse(code, &[], Location::start_of("<input>"));
+    let should_work_tokens: Vec<TokenKind> = Lexer::new("4[5^90~]", &[], Location::start_of("<input>"))
+        .map(|token| token.unwrap().kind)
+        .collect();
+    assert_eq!(
+        vec![
+            TokenKind::Number(4.0),
+            TokenKind::Open('['),
+            TokenKind::Number(5.0),
+            TokenKind::Command(Commands::ForwardCommand),
+            TokenKind::Number(90.0),
+            TokenKind::Command(Commands::TurnCommand),
+            TokenKind::Close(']'),
+        ],
+        should_work_tokens
+    );
+    assert!(parse_code("4[$5^90~]").is_err());
+    assert!(parse_code("[]]").is_err());
+    assert!(parse_code("[}").is_err());
+    assert!(parse_code("[{]}").is_err());
+    assert!(parse_code("[]").is_ok());
+    assert!(parse_code("{}{{}[]}").is_ok());
+    assert!(parse_code("[{[]}]").is_ok());
+}
+#[test]
+fn parse_error_location_test() {
+    let start = Location::start_of("test.drw");
+    let err = parse("1 2\n3[4$]", &[], start.clone()).unwrap_err();
+    assert_eq!(err.at, Location::new(Rc::from("test.drw"), 2, 4));
+    let err = parse("[1\n{2]", &[], start.clone()).unwrap_err();
+    assert_eq!(err.at, Location::new(Rc::from("test.drw"), 2, 3));
+    assert_eq!(err.opened_at, Some(Location::new(Rc::from("test.drw"), 2, 1)));
+    let err = parse("[[]", &[], start).unwrap_err();
+    assert_eq!(err.at.col, 1);
+    //function bodies start after their name and binding
+    let err = parse("]", &[], Location::new(Rc::from("f"), 3, 3));
+    assert_eq!(err.unwrap_err().at, Location::new(Rc::from("f"), 3, 3));
+}
+#[test]
+fn parse_error_display_test() {
+    let err = parse("4[5^}", &[], Location::start_of("a.drw"))
+        .unwrap_err()
+        .with_source("4[5^} # comment");
     assert_eq!(
-        vec!['4', '[', '5', '^', '9', '0', '~', ']'],
-        should_work_tokens.unwrap()
+        err.to_string(),
+        "error: Mismatched brackets: `[` is closed by `}`
+ --> a.drw:1:5
+  |
+1 | 4[5^} # comment
+  |     ^ closed here
+ --> a.drw:1:2
+  |
+1 | 4[5^} # comment
+  |  ^ opened here"
     );
-    assert_eq!(verify("4[|5^90~]".to_string(), Vec::new()).is_ok(), false);
-    let should_not_work: Result<Vec<char>, String> = verify("[]]".to_string(), Vec::new());
-    println!("testing []]");
-    assert_eq!(should_not_work.is_ok(), false);
-    println!("testing [}}");
-    assert_eq!(verify("[}".to_string(), Vec::new()).is_ok(), false);
-    println!("testing [{{]}}");
-    assert_eq!(verify("[{]}".to_string(), Vec::new()).is_ok(), false);
-    println!("testing []");
-    assert_eq!(verify("[]".to_string(), Vec::new()).is_ok(), true);
-    println!("testing {{}}{{{{}}[]}}");
-    assert_eq!(verify("{}{{}[]}".to_string(), Vec::new()).is_ok(), true);
-    println!("testing [{{[]}}]"); //the extra {} are for format!
-    assert_eq!(verify("[{[]}]".to_string(), Vec::new()).is_ok(), true);
+}
+#[test]
+fn quotation_test() {
+    let parse_code = |code: &str| parse(code, &[], Location::start_of("<input>"));
+    assert!(parse_code("\"1[2]'\"3'`").is_ok());
+    assert!(parse_code("\"1[2'").is_err());
+    assert!(parse_code("\"1'2'").is_err());
+    let ast = AST::new("\"1 2+ ¯3 [^]\"F'' `".to_string(), vec!['F']).unwrap();
+    let children = ast.node.children.as_ref().unwrap();
+    assert_eq!(children[0].structure, Some(ControlStructures::Quotation));
+    assert_eq!(children[1].command, Some(Commands::ExecuteCommand));
+    //a bracket's span runs to its closing bracket
+    assert_eq!(&ast.code[children[0].span.clone()], "\"1 2+ ¯3 [^]\"F''");
+    assert_eq!(&ast.code[children[1].span.clone()], "`");
+    let repeat = &children[0].children.as_ref().unwrap()[4];
+    assert_eq!(&ast.code[repeat.span.clone()], "[^]");
+}
+#[test]
+fn number_literal_test() {
+    let numbers = |code: &str| -> Vec<Option<f64>> {
+        AST::new(code.to_string(), Vec::new())
+            .unwrap()
+            .node
+            .children
+            .unwrap()
+            .into_iter()
+            .map(|node| node.number)
+            .collect()
+    };
+    assert_eq!(numbers("0.5 ¯3 1e6 2.5e¯3"), vec![Some(0.5), Some(-3.0), Some(1e6), Some(2.5e-3)]);
+    //without digits on both sides, `.` and `e` are still commands
+    assert_eq!(numbers("1.e"), vec![Some(1.0), None, None]);
+    assert_eq!(numbers(".5"), vec![None, Some(5.0)]);
+    assert_eq!(numbers("2e ¯1"), vec![Some(2.0), None, Some(-1.0)]);
+    assert!(AST::new("¯".to_string(), Vec::new()).is_err());
+    assert!(AST::new("¯.5".to_string(), Vec::new()).is_err());
 }
 #[test]
 fn astnew_test() {
-    let should_work: Result<AST, String> = AST::new("2 2+[5^90~]".to_string(), Vec::new());
-    assert_eq!(should_work.is_ok(), true);
+    let should_work: Result<AST, ParseError> = AST::new("2 2+[5^90~]".to_string(), Vec::new());
+    assert!(should_work.is_ok());
     assert_eq!(
         should_work.unwrap().node.children.clone().unwrap()[2],
         ASTnode {
@@ -348,9 +548,11 @@ fn astnew_test() {
             number: None,
             function: None,
             children: None,
+            location: Location::new(Rc::from("<input>"), 1, 4),
+            span: 3..4,
         }
     );
-    let should_work: Result<AST, String> = AST::new("4[5^90~]".to_string(), Vec::new());
+    let should_work: Result<AST, ParseError> = AST::new("4[5^90~]".to_string(), Vec::new());
     assert_eq!(
         should_work.unwrap().node.children.clone().unwrap()[0],
         ASTnode {
@@ -360,9 +562,11 @@ fn astnew_test() {
             number: Some(4.0),
             function: None,
             children: None,
+            location: Location::new(Rc::from("<input>"), 1, 1),
+            span: 0..1,
         }
     );
-    let should_work: Result<AST, String> = AST::new("91".to_string(), Vec::new());
+    let should_work: Result<AST, ParseError> = AST::new("91".to_string(), Vec::new());
     assert_eq!(
         should_work.unwrap().node.children.clone().unwrap()[0],
         ASTnode {
@@ -372,9 +576,11 @@ fn astnew_test() {
             number: Some(91.0),
             function: None,
             children: None,
+            location: Location::new(Rc::from("<input>"), 1, 1),
+            span: 0..2,
         }
     );
-    let should_work: Result<AST, String> = AST::new("2[180~3[10^90~]]".to_string(), Vec::new());
+    let should_work: Result<AST, ParseError> = AST::new("2[180~3[10^90~]]".to_string(), Vec::new());
     assert_eq!(
         should_work.unwrap().node.children.clone().unwrap()[1]
             .children
@@ -390,6 +596,37 @@ fn astnew_test() {
             number: None,
             children: None,
             function: None,
+            location: Location::new(Rc::from("<input>"), 1, 11),
+            span: 10..11,
         }
     );
 }
+#[test]
+fn lexer_test() {
+    let start = Location::new(Rc::from("<input>"), 1, 1);
+    let tokens: Vec<(TokenKind, usize, Range<usize>)> = Lexer::new("12.5[F\n ¯3]", &['F'], start)
+        .map(|token| token.unwrap())
+        .map(|token| (token.kind, token.span.start.col, token.span.bytes))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenKind::Number(12.5), 1, 0..4),
+            (TokenKind::Open('['), 5, 4..5),
+            (TokenKind::Function('F'), 6, 5..6),
+            (TokenKind::Number(-3.0), 2, 8..11), //¯ is two bytes
+            (TokenKind::Close(']'), 4, 11..12),
+        ]
+    );
+}
+#[test]
+fn deep_nesting_test() {
+    //each bracket is only looked at once, so this doesn't take long
+    let depth = 5000;
+    let code = "1[".repeat(depth) + &"]".repeat(depth);
+    let mut node = AST::new(code, Vec::new()).unwrap().node;
+    for _ in 0..depth {
+        node = node.children.unwrap().pop().unwrap();
+    }
+    assert_eq!(node.children, Some(Vec::new()));
+}
@@ -0,0 +1,634 @@
+//every builtin, written down once: the parser reads its glyphs from here, and `drw help` prints it
+//the README's EBNF and drw.nanorc are checked against it by the tests at the bottom
+
+use crate::ast::{Commands, ControlStructures};
+use std::fmt;
+
+//how a builtin changes the data stack when there is enough on it: it takes `pops` values off then leaves `pushes` on
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StackEffect {
+    pub pops: usize,
+    pub pushes: usize,
+}
+
+//what a builtin does when the stack runs out, since drw fills in a value instead of erroring
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Defaults {
+    Unused,      //it doesn't take anything off the stack
+    Fill(f64),   //each missing value is this
+    Result(f64), //it pushes this instead of its usual result
+    Skip,        //it does nothing
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Builtin {
+    pub glyph: char,
+    pub name: &'static str,
+    pub command: Commands,
+    pub effect: Option<StackEffect>, //None if it depends on what is on the stack or which way the turtle turns
+    pub defaults: Defaults,
+    pub description: &'static str,
+}
+
+//the brackets that make blocks, which are builtins too but aren't commands
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bracket {
+    pub open: char,
+    pub close: char,
+    pub name: &'static str,
+    pub structure: ControlStructures,
+    pub description: &'static str,
+}
+
+const fn effect(pops: usize, pushes: usize) -> Option<StackEffect> {
+    Some(StackEffect { pops, pushes })
+}
+
+pub static BUILTINS: [Builtin; 48] = [
+    Builtin {
+        glyph: '^',
+        name: "Forward",
+        command: Commands::ForwardCommand,
+        effect: effect(1, 0),
+        defaults: Defaults::Skip,
+        description: "Pops one value from stack and goes forward that many steps.",
+    },
+    Builtin {
+        glyph: '~',
+        name: "Turn",
+        command: Commands::TurnCommand,
+        effect: effect(1, 0),
+        defaults: Defaults::Fill(0.0),
+        description: "Pops one value from stack turns counterclockwise that many degrees(or radians).",
+    },
+    Builtin {
+        glyph: '.',
+        name: "Duplicate",
+        command: Commands::DuplicateCommand,
+        effect: effect(1, 2),
+        defaults: Defaults::Result(0.0),
+        description: "Duplicates the top stack value.",
+    },
+    Builtin {
+        glyph: ':',
+        name: "Swap",
+        command: Commands::SwapCommand,
+        effect: effect(2, 2),
+        defaults: Defaults::Fill(0.0),
+        description: "Swaps the two top stack values.",
+    },
+    Builtin {
+        glyph: '!',
+        name: "Pop",
+        command: Commands::PopCommand,
+        effect: effect(1, 0),
+        defaults: Defaults::Skip,
+        description: "Discards the top stack value.",
+    },
+    Builtin {
+        glyph: '+',
+        name: "Add",
+        command: Commands::AddCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Fill(0.0),
+        description: "Adds the top two numbers on the stack.",
+    },
+    Builtin {
+        glyph: '-',
+        name: "Subtract",
+        command: Commands::SubtractCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Fill(0.0),
+        description: "Subtracts the second number on the stack from the top one.",
+    },
+    Builtin {
+        glyph: '*',
+        name: "Multiply",
+        command: Commands::MultiplyCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Fill(1.0),
+        description: "Multiplies the top two numbers on the stack.",
+    },
+    Builtin {
+        glyph: '/',
+        name: "Divide",
+        command: Commands::DivideCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Fill(1.0),
+        description: "Divides the top number on the stack by the one below it. Dividing by 0 gives `,`.",
+    },
+    Builtin {
+        glyph: '%',
+        name: "Modulo",
+        command: Commands::ModuloCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Fill(1.0),
+        description: "The remainder of dividing the top number on the stack by the one below it.",
+    },
+    Builtin {
+        glyph: 'o',
+        name: "Degree mode",
+        command: Commands::DegreeCommand,
+        effect: None,
+        defaults: Defaults::Unused,
+        description: "Switches to degrees for turns, if in degree mode, pushes the number of degrees in a full circle(360). The turtle defaults to degree mode.",
+    },
+    Builtin {
+        glyph: 'r',
+        name: "Radian mode",
+        command: Commands::RadianCommand,
+        effect: None,
+        defaults: Defaults::Unused,
+        description: "Switches to radians for turns, if in radian mode, pushes the number of radians in a full circle(τ).",
+    },
+    Builtin {
+        glyph: 'c',
+        name: "Color",
+        command: Commands::ColorCommand,
+        effect: None,
+        defaults: Defaults::Fill(255.0),
+        description: "Pops 3 values in [0,256) from the stack, or one box of them, and sets them to the turtles color in RGB. The turtle defaults to white pen color.",
+    },
+    Builtin {
+        glyph: 'd',
+        name: "Pen down",
+        command: Commands::PenDownCommand,
+        effect: effect(0, 0),
+        defaults: Defaults::Unused,
+        description: "Puts the turtles pen down. It is down by default.",
+    },
+    Builtin {
+        glyph: 'u',
+        name: "Pen up",
+        command: Commands::PenUpCommand,
+        effect: effect(0, 0),
+        defaults: Defaults::Unused,
+        description: "Pulls the turtles pen up.",
+    },
+    Builtin {
+        glyph: 's',
+        name: "Pen size",
+        command: Commands::SizeCommand,
+        effect: effect(1, 0),
+        defaults: Defaults::Skip,
+        description: "Pops one value off the stack and sets the turtles pen size to that. Size is 1 by default.",
+    },
+    Builtin {
+        glyph: '?',
+        name: "Debug print",
+        command: Commands::DebugCommand,
+        effect: effect(0, 0),
+        defaults: Defaults::Unused,
+        description: "Print out the whole stack without popping it.",
+    },
+    Builtin {
+        glyph: 'P',
+        name: "Power",
+        command: Commands::PowerCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Fill(1.0),
+        description: "Raises the top value on the stack to the value below it.",
+    },
+    Builtin {
+        glyph: 'l',
+        name: "Log",
+        command: Commands::LogCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Fill(std::f64::consts::E),
+        description: "Takes the logarithm of the top stack value of base the value below it.",
+    },
+    Builtin {
+        glyph: 'e',
+        name: "E",
+        command: Commands::EulerNumCommand,
+        effect: effect(0, 1),
+        defaults: Defaults::Unused,
+        description: "Pushes e.",
+    },
+    Builtin {
+        glyph: 'q',
+        name: "Square root",
+        command: Commands::SquareRootCommand,
+        effect: effect(1, 1),
+        defaults: Defaults::Fill(1.0),
+        description: "Takes the square root of the top of the stack.",
+    },
+    Builtin {
+        glyph: 'S',
+        name: "Sine",
+        command: Commands::SineCommand,
+        effect: effect(1, 1),
+        defaults: Defaults::Fill(0.0),
+        description: "Takes the sine of the top of the stack.",
+    },
+    Builtin {
+        glyph: 'C',
+        name: "Ceiling",
+        command: Commands::CeilingCommand,
+        effect: effect(1, 1),
+        defaults: Defaults::Fill(0.0),
+        description: "Rounds to the number above the current number.",
+    },
+    Builtin {
+        glyph: 'f',
+        name: "Floor",
+        command: Commands::FloorCommand,
+        effect: effect(1, 1),
+        defaults: Defaults::Fill(0.0),
+        description: "Rounds to the number below the current number.",
+    },
+    Builtin {
+        glyph: 'R',
+        name: "Round",
+        command: Commands::RoundCommand,
+        effect: effect(1, 1),
+        defaults: Defaults::Fill(1.0),
+        description: "Rounds to the nearest integer.",
+    },
+    Builtin {
+        glyph: '>',
+        name: "Less than",
+        command: Commands::LessThanCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Result(0.0),
+        description: "Pops two numbers from the stack, pushing 1 if the top one is less than the one below it, pushing 0 otherwise.",
+    },
+    Builtin {
+        glyph: '<',
+        name: "Greater than",
+        command: Commands::GreaterThanCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Result(0.0),
+        description: "Pops two numbers from the stack, pushing 1 if the top one is more than the one below it, pushing 0 otherwise.",
+    },
+    Builtin {
+        glyph: '=',
+        name: "Equal",
+        command: Commands::EqualCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Result(0.0),
+        description: "Pops two numbers from the stack, pushing 1 if they are equal, pushing 0 otherwise.",
+    },
+    Builtin {
+        glyph: '(',
+        name: "Dip",
+        command: Commands::DipCommand,
+        effect: effect(1, 0),
+        defaults: Defaults::Fill(0.0),
+        description: "Temporarily pops the top stack value to an inaccessible stack that can be thought of as \"above\" the main stack.",
+    },
+    Builtin {
+        glyph: ')',
+        name: "Undip",
+        command: Commands::UndipCommand,
+        effect: effect(0, 1),
+        defaults: Defaults::Fill(0.0),
+        description: "Pushes one value back from the upper stack that `(` put it on.",
+    },
+    Builtin {
+        glyph: 'B',
+        name: "Box",
+        command: Commands::BoxCommand,
+        effect: effect(3, 1),
+        defaults: Defaults::Fill(0.0),
+        description: "Takes three values (including boxes) off the stack and combines them into a \"box\" which can be manipulated just like any value on the stack.",
+    },
+    Builtin {
+        glyph: 'U',
+        name: "Unbox",
+        command: Commands::UnboxCommand,
+        effect: effect(1, 3),
+        defaults: Defaults::Fill(0.0),
+        description: "Puts the three values in a box back on the stack. Unboxing `,` gives three `,`s.",
+    },
+    Builtin {
+        glyph: 'I',
+        name: "Is box",
+        command: Commands::IsBoxCommand,
+        effect: effect(1, 1),
+        defaults: Defaults::Result(0.0),
+        description: "Pushes 1 if the value on top of the stack is a box, 0 otherwise.",
+    },
+    Builtin {
+        glyph: '@',
+        name: "Rot",
+        command: Commands::RotCommand,
+        effect: effect(3, 3),
+        defaults: Defaults::Fill(0.0),
+        description: "Puts the third stack value on top of the stack.",
+    },
+    Builtin {
+        glyph: '&',
+        name: "Unrot",
+        command: Commands::UnrotCommand,
+        effect: effect(3, 3),
+        defaults: Defaults::Fill(0.0),
+        description: "Puts the top stack value in the third value on the stack.",
+    },
+    Builtin {
+        glyph: ',',
+        name: "Nil",
+        command: Commands::NilCommand,
+        effect: effect(0, 1),
+        defaults: Defaults::Unused,
+        description: "Pushes Nil to the stack, a value which means nothing. Math on `,` gives `,`, `,[...]` does nothing, and `,` is considered falsy.",
+    },
+    Builtin {
+        glyph: ';',
+        name: "Is nil",
+        command: Commands::IsNilCommand,
+        effect: effect(1, 1),
+        defaults: Defaults::Fill(0.0),
+        description: "Returns `1` if the top stack value is `,`, returning `0` otherwise, pervading over boxes.",
+    },
+    Builtin {
+        glyph: '|',
+        name: "Match",
+        command: Commands::MatchCommand,
+        effect: effect(2, 1),
+        defaults: Defaults::Result(0.0),
+        description: "Tests to see if the two top values of the stack exactly match. It does not follow any rules for pervasion or nil.",
+    },
+    Builtin {
+        glyph: '`',
+        name: "Execute",
+        command: Commands::ExecuteCommand,
+        effect: None,
+        defaults: Defaults::Skip,
+        description: "Pops a quotation and runs it. Anything else is left on the stack untouched.",
+    },
+    Builtin {
+        glyph: 'x',
+        name: "Get x",
+        command: Commands::GetXCommand,
+        effect: effect(0, 1),
+        defaults: Defaults::Unused,
+        description: "Pushes the turtle's x position, in window coordinates. The turtle starts at `400 225`, the middle of the window.",
+    },
+    Builtin {
+        glyph: 'y',
+        name: "Get y",
+        command: Commands::GetYCommand,
+        effect: effect(0, 1),
+        defaults: Defaults::Unused,
+        description: "Pushes the turtle's y position, in window coordinates. y goes down.",
+    },
+    Builtin {
+        glyph: 'X',
+        name: "Set x",
+        command: Commands::SetXCommand,
+        effect: effect(1, 0),
+        defaults: Defaults::Skip,
+        description: "Pops one value and moves the turtle straight to that x position. Like `^`, this draws a line if the pen is down and `,` does nothing.",
+    },
+    Builtin {
+        glyph: 'Y',
+        name: "Set y",
+        command: Commands::SetYCommand,
+        effect: effect(1, 0),
+        defaults: Defaults::Skip,
+        description: "Pops one value and moves the turtle straight to that y position. Like `^`, this draws a line if the pen is down and `,` does nothing.",
+    },
+    Builtin {
+        glyph: 'h',
+        name: "Get heading",
+        command: Commands::GetHeadingCommand,
+        effect: effect(0, 1),
+        defaults: Defaults::Unused,
+        description: "Pushes the direction the turtle is facing in degrees (or radians), from 0 up to a full circle. 0 is facing right.",
+    },
+    Builtin {
+        glyph: 'H',
+        name: "Set heading",
+        command: Commands::SetHeadingCommand,
+        effect: effect(1, 0),
+        defaults: Defaults::Skip,
+        description: "Pops one value and turns the turtle to face that many degrees (or radians) from 0.",
+    },
+    Builtin {
+        glyph: 'a',
+        name: "Begin fill",
+        command: Commands::BeginFillCommand,
+        effect: effect(0, 0),
+        defaults: Defaults::Unused,
+        description: "Starts a filled shape at the turtle's position. Every point the turtle moves to after this becomes a corner of the shape.",
+    },
+    Builtin {
+        glyph: 'z',
+        name: "End fill",
+        command: Commands::EndFillCommand,
+        effect: effect(0, 0),
+        defaults: Defaults::Unused,
+        description: "Closes the shape back to where `a` was used and fills anything inside the outline (the nonzero rule) with the current color.",
+    },
+    Builtin {
+        glyph: 'Z',
+        name: "End even-odd fill",
+        command: Commands::EndEvenOddFillCommand,
+        effect: effect(0, 0),
+        defaults: Defaults::Unused,
+        description: "Like `z`, but leaves holes where the outline is inside itself an even number of times (the even-odd rule).",
+    },
+];
+
+pub static BRACKETS: [Bracket; 3] = [
+    Bracket {
+        open: '[',
+        close: ']',
+        name: "Repeat loop",
+        structure: ControlStructures::RepeatLoop,
+        description: "Pops one number off the stack, and executes the code within that many times. Can be used as an if statement by `<Conditonal>[<whatever code>]`.",
+    },
+    Bracket {
+        open: '{',
+        close: '}',
+        name: "While loop",
+        structure: ControlStructures::WhileLoop,
+        description: "Pops off at the opening `{` and runs the code within while what it popped is truthy, popping again each time the code finishes.",
+    },
+    Bracket {
+        open: '"',
+        close: '\'',
+        name: "Quotation",
+        structure: ControlStructures::Quotation,
+        description: "Pushes the code within `\"...'` to the stack without running it. `` ` `` runs it.",
+    },
+];
+
+pub fn find(glyph: char) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.glyph == glyph)
+}
+
+pub fn glyph(command: &Commands) -> char {
+    BUILTINS
+        .iter()
+        .find(|builtin| builtin.command == *command)
+        .unwrap()
+        .glyph //every command is in the table
+}
+
+pub fn find_bracket(c: char) -> Option<&'static Bracket> {
+    BRACKETS
+        .iter()
+        .find(|bracket| bracket.open == c || bracket.close == c)
+}
+
+pub fn bracket_for(structure: &ControlStructures) -> &'static Bracket {
+    BRACKETS
+        .iter()
+        .find(|bracket| bracket.structure == *structure)
+        .unwrap()
+}
+
+//what `drw help <glyph>` prints, if the glyph is a builtin
+pub fn help(glyph: char) -> Option<String> {
+    match (find(glyph), find_bracket(glyph)) {
+        (Some(builtin), _) => Some(builtin.to_string()),
+        (_, Some(bracket)) => Some(bracket.to_string()),
+        _ => None,
+    }
+}
+
+//every builtin, for `drw help --all`
+pub fn help_all() -> String {
+    let commands = BUILTINS.iter().map(Builtin::to_string);
+    let brackets = BRACKETS.iter().map(Bracket::to_string);
+    commands.chain(brackets).collect::<Vec<String>>().join("\n")
+}
+
+//says that a command or function would run out of stack, for `drw check` and --strict
+pub fn running_out(name: char, takes: usize, have: usize) -> String {
+    let values = if takes == 1 { "1 value".to_string() } else { format!("{takes} values") };
+    match have {
+        0 => format!("`{name}` takes {values} but the stack is empty here"),
+        1 => format!("`{name}` takes {values} but only 1 is on the stack here"),
+        _ => format!("`{name}` takes {values} but only {have} are on the stack here"),
+    }
+}
+
+impl fmt::Display for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", self.glyph, self.name)?;
+        match self.effect {
+            Some(StackEffect { pops, pushes }) => write!(f, "  takes {pops}, leaves {pushes}")?,
+            None => write!(
+                f,
+                "  takes and leaves a different amount depending on what it is given"
+            )?,
+        }
+        match self.defaults {
+            Defaults::Unused => writeln!(f)?,
+            Defaults::Fill(value) => writeln!(f, ". missing values are {value}")?,
+            Defaults::Result(value) => {
+                writeln!(f, ". with too little on the stack it leaves {value}")?
+            }
+            Defaults::Skip => writeln!(f, ". with nothing on the stack it does nothing")?,
+        }
+        writeln!(f, "  {}", self.description)
+    }
+}
+
+impl fmt::Display for Bracket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}...{} {}", self.open, self.close, self.name)?;
+        writeln!(f, "  {}", self.description)
+    }
+}
+
+#[cfg(test)]
+fn run(code: &str) -> (String, String) {
+    let mut interpreter = crate::Interpreter::new();
+    interpreter.keep_printed();
+    interpreter.run("<test>", code).unwrap();
+    (interpreter.stack_string(), interpreter.turtle().dump())
+}
+
+#[test]
+fn table_test() {
+    for (idx, builtin) in BUILTINS.iter().enumerate() {
+        assert!(
+            find(builtin.glyph) == Some(builtin),
+            "{} is in the table twice",
+            builtin.glyph
+        );
+        assert!(
+            BUILTINS[..idx]
+                .iter()
+                .all(|other| other.command != builtin.command)
+        );
+        assert!(find_bracket(builtin.glyph).is_none());
+    }
+    assert_eq!(
+        help('+').unwrap(),
+        "+ Add\n  takes 2, leaves 1. missing values are 0\n  Adds the top two numbers on the stack.\n"
+    );
+    assert!(help('\'').unwrap().starts_with("\"...' Quotation\n"));
+    assert_eq!(help('F'), None);
+}
+
+#[test]
+fn effects_test() {
+    //the table has to say what the interpreter really does
+    for builtin in &BUILTINS {
+        let glyph = builtin.glyph;
+        if let Some(StackEffect { pops, pushes }) = builtin.effect {
+            let (stack, _) = run(&format!("1 2 3 4{glyph}"));
+            assert_eq!(
+                stack.lines().count() - 2,
+                4 - pops + pushes,
+                "{glyph} doesn't take {pops} and leave {pushes}"
+            );
+        }
+        let (empty, drawing) = run(&glyph.to_string());
+        match builtin.defaults {
+            Defaults::Fill(value) if builtin.effect.is_some_and(|effect| effect.pops > 0) => {
+                let pops = builtin.effect.unwrap().pops;
+                assert_eq!(
+                    (empty, drawing),
+                    run(&format!("{}{glyph}", format!("{value} ").repeat(pops))),
+                    "{glyph}"
+                );
+            }
+            Defaults::Result(value) => {
+                assert_eq!(empty, format!("bottom\n{value}\ntop\n"), "{glyph}")
+            }
+            Defaults::Skip => assert_eq!((empty, drawing), run(""), "{glyph}"),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn docs_agree_test() {
+    let mut glyphs: Vec<char> = BUILTINS.iter().map(|builtin| builtin.glyph).collect();
+    glyphs.extend(
+        BRACKETS
+            .iter()
+            .flat_map(|bracket| [bracket.open, bracket.close]),
+    );
+    glyphs.sort();
+    //every glyph in quotes on the README's <builtin> line
+    let readme = include_str!("../README.md");
+    let line = readme
+        .lines()
+        .find(|line| line.starts_with("<builtin> <="))
+        .unwrap();
+    let chars: Vec<char> = line.chars().collect();
+    let mut documented: Vec<char> = chars
+        .windows(3)
+        .filter(|quoted| matches!(quoted[0], '"' | '\'') && quoted[2] == quoted[0])
+        .map(|quoted| quoted[1])
+        .collect();
+    documented.sort();
+    assert_eq!(documented, glyphs);
+    //drw.nanorc colors each one
+    let nanorc = include_str!("../drw.nanorc").replace('\\', "");
+    let colored: String = nanorc
+        .lines()
+        .take_while(|line| !line.starts_with("# Numbers"))
+        .filter(|line| line.starts_with("color"))
+        .collect();
+    for glyph in glyphs {
+        assert!(colored.contains(glyph), "drw.nanorc doesn't color {glyph}");
+    }
+}
@@ -0,0 +1,238 @@
+//compiles syntax trees into one flat list of instructions, so the interpreter can run them in a loop instead of walking the tree
+//jumps and quotations point at indices in the list, and calls go through a table of where each function starts
+
+use crate::ast;
+use crate::builtins;
+use crate::item;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Instruction {
+    Push(f64),
+    Command(ast::Commands), //anything that only touches the stacks and the turtle
+    PushQuotation(Rc<item::Quotation>, usize), //the quotation's code comes next, so this jumps past it
+    Execute,
+    Call(usize), //index into the function table
+    Return,
+    RepeatStart(usize), //pops how many times to go, jumping past the loop if it is 0
+    RepeatEnd(usize),   //counts down, jumping back to the start of the loop if there are more times to go
+    WhileStart(usize),  //pops the condition, jumping past the loop if it is falsy
+    WhileEnd(usize),    //jumps back to the WhileStart
+    Halt,
+}
+
+//where an instruction came from, for errors and --trace
+#[derive(Clone, PartialEq, Debug)]
+pub struct Origin {
+    pub location: ast::Location,
+    pub code: Rc<str>, //the code it was compiled from, which is only sliced into when the text is needed
+    pub span: Range<usize>,
+}
+
+impl Origin {
+    //the instruction's code, like `+`, a whole quotation, or just the opening bracket of a loop
+    pub fn text(&self) -> &str {
+        &self.code[self.span.clone()]
+    }
+}
+
+//everything compiled so far. code is only taken off the end, once nothing can point into it anymore
+pub struct Program {
+    pub code: Vec<Instruction>,
+    pub origins: Vec<Origin>, //one per instruction
+    pub functions: Vec<Option<usize>>, //where each function starts, None until it is defined
+    ends: Vec<usize>, //where each function's code ends, to tell if it was the last thing compiled
+    function_indices: HashMap<char, usize>,
+}
+
+impl Program {
+    pub fn new() -> Self {
+        Program {
+            code: Vec::new(),
+            origins: Vec::new(),
+            functions: Vec::new(),
+            ends: Vec::new(),
+            function_indices: HashMap::new(),
+        }
+    }
+    //the function's slot in the table, making one if this is the first time the name is used
+    fn function_index(&mut self, name: char) -> usize {
+        if let Some(idx) = self.function_indices.get(&name) {
+            return *idx;
+        }
+        self.functions.push(None);
+        self.ends.push(0);
+        self.function_indices.insert(name, self.functions.len() - 1);
+        self.functions.len() - 1
+    }
+    //compiles a function's body and points its name at it, replacing any older definition
+    pub fn define(&mut self, name: char, body: &ast::AST) {
+        let start = self.code.len();
+        self.compile(body.node.children.as_ref().unwrap(), &body.code);
+        let end = self.origin_of_end(body);
+        self.emit(Instruction::Return, end);
+        let idx = self.function_index(name);
+        self.functions[idx] = Some(start);
+        self.ends[idx] = self.code.len();
+    }
+    //where the function starts, if its code is the last thing compiled and so could be written over
+    pub fn last_function(&self, name: char) -> Option<usize> {
+        let idx = *self.function_indices.get(&name)?;
+        self.functions[idx].filter(|_| self.ends[idx] == self.code.len())
+    }
+    //drops everything compiled from len on. functions that started there are undefined until compiled again
+    pub fn truncate(&mut self, len: usize) {
+        self.code.truncate(len);
+        self.origins.truncate(len);
+        for start in &mut self.functions {
+            if start.is_some_and(|start| start >= len) {
+                *start = None;
+            }
+        }
+    }
+    //compiles code to be run, returning where it starts
+    pub fn add_main(&mut self, body: &ast::AST) -> usize {
+        let start = self.code.len();
+        self.compile(body.node.children.as_ref().unwrap(), &body.code);
+        let end = self.origin_of_end(body);
+        self.emit(Instruction::Halt, end);
+        start
+    }
+    fn origin_of_end(&self, body: &ast::AST) -> Origin {
+        Origin {
+            location: body.node.location.clone(),
+            code: body.code.clone(),
+            span: 0..0,
+        }
+    }
+    fn emit(&mut self, instruction: Instruction, origin: Origin) -> usize {
+        self.code.push(instruction);
+        self.origins.push(origin);
+        self.code.len() - 1
+    }
+    //brackets are compiled with a loop instead of recursively, so deep nesting can't overflow the stack.
+    //pending is what is left to do, next last: Ok for nodes to compile and Err for the start of a bracket to close
+    fn compile(&mut self, nodes: &[ast::ASTnode], code: &Rc<str>) {
+        let mut pending: Vec<Result<&ast::ASTnode, (usize, Origin)>> = nodes.iter().rev().map(Ok).collect();
+        while let Some(next) = pending.pop() {
+            let node = match next {
+                Ok(node) => node,
+                Err((start, origin)) => {
+                    //jump targets aren't known until the body is compiled, so they are filled in now
+                    self.code[start] = match &self.code[start] {
+                        Instruction::RepeatStart(_) => {
+                            let end = self.emit(Instruction::RepeatEnd(start + 1), origin);
+                            Instruction::RepeatStart(end + 1)
+                        }
+                        Instruction::WhileStart(_) => {
+                            let end = self.emit(Instruction::WhileEnd(start), origin);
+                            Instruction::WhileStart(end + 1)
+                        }
+                        Instruction::PushQuotation(quotation, _) => {
+                            let quotation = quotation.clone();
+                            let end = self.emit(Instruction::Return, origin);
+                            Instruction::PushQuotation(quotation, end + 1)
+                        }
+                        _ => unreachable!(), //only brackets are closed
+                    };
+                    continue;
+                }
+            };
+            let mut origin = Origin {
+                location: node.location.clone(),
+                code: code.clone(),
+                span: node.span.clone(),
+            };
+            match node.nodetype {
+                ast::ASTnodeType::Number => {
+                    self.emit(Instruction::Push(node.number.unwrap()), origin);
+                }
+                ast::ASTnodeType::Command => {
+                    let instruction = match node.command.as_ref().unwrap() {
+                        ast::Commands::ExecuteCommand => Instruction::Execute,
+                        command => Instruction::Command(command.clone()),
+                    };
+                    self.emit(instruction, origin);
+                }
+                ast::ASTnodeType::Function => {
+                    let idx = self.function_index(node.function.unwrap());
+                    self.emit(Instruction::Call(idx), origin);
+                }
+                ast::ASTnodeType::ControlStructure => {
+                    let structure = node.structure.as_ref().unwrap();
+                    let bracket = builtins::bracket_for(structure);
+                    let insides = node.span.start + bracket.open.len_utf8()..node.span.end - bracket.close.len_utf8();
+                    let instruction = match structure {
+                        ast::ControlStructures::RepeatLoop => Instruction::RepeatStart(0),
+                        ast::ControlStructures::WhileLoop => Instruction::WhileStart(0),
+                        ast::ControlStructures::Quotation => {
+                            let quotation = item::Quotation {
+                                code: code.clone(),
+                                span: insides.clone(),
+                                start: self.code.len() + 1,
+                            };
+                            Instruction::PushQuotation(Rc::new(quotation), 0)
+                        }
+                    };
+                    if *structure != ast::ControlStructures::Quotation {
+                        //loops are just their opening bracket, since their insides are run separately
+                        origin.span.end = insides.start;
+                    }
+                    let start = self.emit(instruction, origin.clone());
+                    pending.push(Err((start, origin)));
+                    pending.extend(node.children.as_ref().unwrap().iter().rev().map(Ok));
+                }
+                ast::ASTnodeType::Container => unreachable!(), //containers are only ever the root
+            }
+        }
+    }
+}
+
+impl Default for Program {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn compile_test() {
+    let main = ast::AST::new("2[1 F]\"+'`{.}".to_string(), vec!['F']).unwrap();
+    let mut program = Program::new();
+    program.define('F', &ast::AST::new("^".to_string(), Vec::new()).unwrap());
+    let start = program.add_main(&main);
+    assert_eq!(start, 2);
+    let code = &program.code[start..];
+    assert_eq!(code[0], Instruction::Push(2.0));
+    assert_eq!(code[1], Instruction::RepeatStart(start + 5));
+    assert_eq!(code[3], Instruction::Call(0));
+    assert_eq!(code[4], Instruction::RepeatEnd(start + 2));
+    let Instruction::PushQuotation(quotation, after) = &code[5] else {
+        panic!("expected a quotation, got {:?}", code[5]);
+    };
+    assert_eq!((quotation.start, *after), (start + 6, start + 8));
+    assert_eq!(code[7], Instruction::Return);
+    assert_eq!(code[8], Instruction::Execute);
+    assert_eq!(code[9], Instruction::WhileStart(start + 12));
+    assert_eq!(code[11], Instruction::WhileEnd(start + 9));
+    assert_eq!(code[12], Instruction::Halt);
+    assert_eq!(program.functions, vec![Some(0)]);
+    assert_eq!(program.origins[start + 1].text(), "[");
+    assert_eq!(program.origins[start + 5].text(), "\"+'");
+    assert_eq!(quotation.text(), "+");
+}
+#[test]
+fn deep_quotation_test() {
+    //each quotation's text is sliced out of the code when it is needed, instead of being written out again for
+    //every quotation it is in, so this compiles quickly
+    let depth = 5000;
+    let code = "\"".repeat(depth) + "1" + &"'".repeat(depth);
+    let mut program = Program::new();
+    let start = program.add_main(&ast::AST::new(code, Vec::new()).unwrap());
+    let Instruction::PushQuotation(quotation, after) = &program.code[start + 1] else {
+        panic!("expected a quotation, got {:?}", program.code[start + 1]);
+    };
+    assert_eq!(quotation.text(), "\"".repeat(depth - 2) + "1" + &"'".repeat(depth - 2));
+    assert_eq!(*after, start + 2 * depth);
+}
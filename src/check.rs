@@ -0,0 +1,654 @@
+//`drw check`: works out how many values each function and the main code take off the stack and leave on it without
+//running them, and warns wherever a command would run out of stack and quietly use one of its defaults instead.
+//code whose effect depends on things only known while running, like a loop that runs an unknown number of times
+//and changes how much is on the stack, stops the analysis there instead of guessing
+
+use crate::ast::{self, ASTnode, ASTnodeType, Commands, ControlStructures};
+use crate::builtins::{self, Defaults, StackEffect};
+use crate::turtle::TurnModes;
+use std::collections::HashMap;
+use std::fmt;
+
+const MAX_UNROLL: f64 = 1000.0; //repeat loops with a number written before them are followed that many times, up to this
+const MAX_STEPS: usize = 1_000_000; //nodes looked at before giving up, so unrolled loops can't take forever
+const MAX_NESTING: usize = 100; //quotations run by quotations
+
+//what is known about a value on the stack
+#[derive(Clone, PartialEq, Debug)]
+enum Value<'a> {
+    Number(Option<f64>), //the number, if it is always the same
+    Nil,
+    Box,
+    Quotation(&'a [ASTnode]),
+    Unknown,
+}
+
+//the stacks at one point in some code
+#[derive(Clone, PartialEq, Debug)]
+struct State<'a> {
+    stack: Vec<Value<'a>>, //what the code pushed itself, top last
+    taken: usize, //how many values it took from below where it started, which are a function's arguments
+    dip: Vec<Value<'a>>,
+    mode: Option<TurnModes>, //None once it could be either
+}
+
+//what calling a function does, when it is called in some turn mode
+#[derive(Clone, PartialEq, Debug)]
+struct Summary {
+    effect: StackEffect,
+    mode: Option<TurnModes>,
+}
+
+//a command that would run out of stack
+#[derive(Clone, PartialEq, Debug)]
+pub struct Warning {
+    pub message: String,
+    pub at: ast::Location,
+    source_line: Option<String>,
+}
+
+//everything `drw check` found in a file
+#[derive(Clone, PartialEq, Debug)]
+pub struct Report {
+    pub functions: Vec<(char, Option<StackEffect>)>, //None if it couldn't be worked out
+    pub main: Option<Option<StackEffect>>,           //None if there is no main code
+    pub warnings: Vec<Warning>,
+}
+
+impl Warning {
+    pub fn with_source(mut self, text: &str) -> Self {
+        self.source_line = text.lines().nth(self.at.line - 1).map(str::to_string);
+        self
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "warning: {}", self.message)?;
+        write!(f, " --> {}", self.at)?;
+        if self.source_line.is_some() {
+            writeln!(f)?;
+            ast::write_snippet(f, &self.at, self.source_line.as_deref(), "")?;
+        }
+        Ok(())
+    }
+}
+
+fn effect_text(effect: &Option<StackEffect>) -> String {
+    match effect {
+        Some(StackEffect { pops, pushes }) => format!("takes {pops}, leaves {pushes}"),
+        None => "depends on what happens while it runs".to_string(),
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, effect) in &self.functions {
+            writeln!(f, "{name} {}", effect_text(effect))?;
+        }
+        match self.main {
+            Some(Some(StackEffect { pushes, .. })) => writeln!(f, "the main code leaves {pushes}")?,
+            Some(None) => writeln!(
+                f,
+                "what the main code leaves depends on what happens while it runs"
+            )?,
+            None => {}
+        }
+        for warning in &self.warnings {
+            writeln!(f, "{warning}")?;
+        }
+        Ok(())
+    }
+}
+
+//what a builtin does instead, for its warning
+fn instead(defaults: Defaults) -> String {
+    match defaults {
+        Defaults::Fill(value) => format!("the missing ones are filled in with {value}"),
+        Defaults::Result(value) => format!("it leaves {value} instead"),
+        Defaults::Skip => "it does nothing instead".to_string(),
+        Defaults::Unused => unreachable!(), //it doesn't take anything
+    }
+}
+
+fn dyadic(command: &Commands) -> Option<fn(f64, f64) -> f64> {
+    Some(match command {
+        Commands::AddCommand => |a, b| a + b,
+        Commands::SubtractCommand => |a, b| a - b,
+        Commands::MultiplyCommand => |a, b| a * b,
+        Commands::DivideCommand => |a, b| a / b,
+        Commands::ModuloCommand => |a, b| a % b,
+        Commands::PowerCommand => |a, b| a.powf(b),
+        Commands::LogCommand => |a, b| a.log(b),
+        Commands::LessThanCommand => |a, b| if a < b { 1.0 } else { 0.0 },
+        Commands::GreaterThanCommand => |a, b| if a > b { 1.0 } else { 0.0 },
+        Commands::EqualCommand => |a, b| if a == b { 1.0 } else { 0.0 },
+        _ => return None,
+    })
+}
+
+fn monadic(command: &Commands) -> Option<fn(f64) -> f64> {
+    Some(match command {
+        Commands::SquareRootCommand => f64::sqrt,
+        Commands::SineCommand => f64::sin,
+        Commands::CeilingCommand => f64::ceil,
+        Commands::FloorCommand => f64::floor,
+        Commands::RoundCommand => f64::round,
+        _ => return None,
+    })
+}
+
+//math on numbers that are always the same gives a number that is always the same, or nil like the interpreter does
+fn math<'a>(f: impl Fn(&[f64]) -> f64, args: &[Value<'a>]) -> Value<'a> {
+    if args
+        .iter()
+        .any(|arg| matches!(arg, Value::Nil | Value::Quotation(_)))
+    {
+        return Value::Nil;
+    }
+    let numbers: Option<Vec<f64>> = args
+        .iter()
+        .map(|arg| match arg {
+            Value::Number(number) => *number,
+            _ => None,
+        })
+        .collect();
+    match numbers.map(|numbers| f(&numbers)) {
+        Some(result) if result.is_finite() => Value::Number(Some(result)),
+        Some(_) => Value::Nil,
+        None => Value::Unknown,
+    }
+}
+
+//whether `[` would run its loop some number of times, None if that can't be known yet
+fn repeat_count(count: &Value) -> Option<f64> {
+    match count {
+        Value::Number(Some(count)) => Some(*count),
+        Value::Nil => Some(0.0),
+        _ => None,
+    }
+}
+
+//the same stacks with anything that differs between them forgotten, for a loop that might have run or not
+fn merge<'a>(a: &State<'a>, b: &State<'a>) -> Option<State<'a>> {
+    if a.stack.len() != b.stack.len() || a.dip.len() != b.dip.len() || a.taken != b.taken {
+        return None;
+    }
+    let forget = |a: &[Value<'a>], b: &[Value<'a>]| -> Vec<Value<'a>> {
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| if a == b { a.clone() } else { Value::Unknown })
+            .collect()
+    };
+    Some(State {
+        stack: forget(&a.stack, &b.stack),
+        taken: a.taken,
+        dip: forget(&a.dip, &b.dip),
+        mode: if a.mode == b.mode {
+            a.mode.clone()
+        } else {
+            None
+        },
+    })
+}
+
+//checks the main code and every function it can reach, and reports on the functions in `listed`, in that order
+pub fn check(main: &ast::AST, functions: &HashMap<char, ast::AST>, listed: &[char]) -> Report {
+    let mut checker = Checker {
+        functions,
+        summaries: HashMap::new(),
+        warnings: Vec::new(),
+        steps: 0,
+        nesting: 0,
+    };
+    let nodes = main.node.children.as_deref().unwrap();
+    let state = State {
+        stack: Vec::new(),
+        taken: 0,
+        dip: Vec::new(),
+        mode: Some(TurnModes::DEGREE), //what the turtle starts in
+    };
+    let main = match nodes.is_empty() {
+        true => None,
+        false => Some(checker.run(nodes, state, false).map(|state| StackEffect {
+            pops: 0,
+            pushes: state.stack.len(),
+        })),
+    };
+    let functions = listed
+        .iter()
+        .map(|name| {
+            checker.steps = 0; //each gets the whole budget
+            let summary = checker.summary(*name, Some(TurnModes::DEGREE));
+            (*name, summary.map(|summary| summary.effect))
+        })
+        .collect();
+    let mut warnings = checker.warnings;
+    warnings.sort_by_key(|warning| (warning.at.line, warning.at.col));
+    Report {
+        functions,
+        main,
+        warnings,
+    }
+}
+
+struct Checker<'a> {
+    functions: &'a HashMap<char, ast::AST>,
+    summaries: HashMap<(char, Option<TurnModes>), Option<Summary>>, //None while one is being worked out, so recursion is unknown
+    warnings: Vec<Warning>,
+    steps: usize,
+    nesting: usize,
+}
+
+impl<'a> Checker<'a> {
+    fn warn(&mut self, message: String, at: &ast::Location) {
+        let warning = Warning {
+            message,
+            at: at.clone(),
+            source_line: None,
+        };
+        //a loop that is followed more than once finds the same thing each time
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+    //in a function, taking more than it pushed takes one of its arguments. in the main code there is nothing there
+    fn pop(&self, state: &mut State<'a>, arguments: bool) -> Option<Value<'a>> {
+        if let Some(value) = state.stack.pop() {
+            return Some(value);
+        }
+        if arguments {
+            state.taken += 1;
+            return Some(Value::Unknown);
+        }
+        None
+    }
+    fn summary(&mut self, name: char, mode: Option<TurnModes>) -> Option<Summary> {
+        let key = (name, mode.clone());
+        if let Some(summary) = self.summaries.get(&key) {
+            return summary.clone();
+        }
+        self.summaries.insert(key.clone(), None);
+        let body = self.functions.get(&name)?.node.children.as_deref().unwrap();
+        let state = State {
+            stack: Vec::new(),
+            taken: 0,
+            dip: Vec::new(),
+            mode,
+        };
+        let summary = self
+            .run(body, state, true)
+            .filter(|state| state.dip.is_empty()) //it left something for its caller to undip
+            .map(|state| Summary {
+                effect: StackEffect {
+                    pops: state.taken,
+                    pushes: state.stack.len(),
+                },
+                mode: state.mode,
+            });
+        self.summaries.insert(key, summary.clone());
+        summary
+    }
+    //follows some code from a state, returning the state after it or None if it can't be known
+    fn run(
+        &mut self,
+        nodes: &'a [ASTnode],
+        mut state: State<'a>,
+        arguments: bool,
+    ) -> Option<State<'a>> {
+        for node in nodes {
+            self.steps += 1;
+            if self.steps > MAX_STEPS {
+                return None;
+            }
+            state = self.node(node, state, arguments)?;
+        }
+        Some(state)
+    }
+    fn node(
+        &mut self,
+        node: &'a ASTnode,
+        mut state: State<'a>,
+        arguments: bool,
+    ) -> Option<State<'a>> {
+        match node.nodetype {
+            ASTnodeType::Number => state.stack.push(Value::Number(node.number)),
+            ASTnodeType::Command => return self.command(node, state, arguments),
+            ASTnodeType::Function => {
+                let name = node.function.unwrap();
+                let summary = self.summary(name, state.mode.clone())?;
+                let have = state.stack.len();
+                let pops = summary.effect.pops;
+                if have < pops {
+                    if arguments {
+                        state.taken += pops - have;
+                    } else {
+                        self.warn(
+                            format!(
+                                "{}, so it runs out and its commands use their defaults",
+                                builtins::running_out(name, pops, have)
+                            ),
+                            &node.location,
+                        );
+                    }
+                }
+                state.stack.truncate(have.saturating_sub(pops));
+                state
+                    .stack
+                    .extend((0..summary.effect.pushes).map(|_| Value::Unknown));
+                state.mode = summary.mode;
+            }
+            ASTnodeType::ControlStructure => {
+                let body = node.children.as_deref().unwrap();
+                match node.structure.as_ref().unwrap() {
+                    ControlStructures::Quotation => state.stack.push(Value::Quotation(body)),
+                    ControlStructures::RepeatLoop => {
+                        let Some(count) = self.pop(&mut state, arguments) else {
+                            self.warn(
+                                "`[` takes how many times to run but the stack is empty here, so it runs 0 times".to_string(),
+                                &node.location,
+                            );
+                            return Some(state);
+                        };
+                        return self.repeat(body, state, repeat_count(&count), arguments);
+                    }
+                    ControlStructures::WhileLoop => {
+                        let Some(condition) = self.pop(&mut state, arguments) else {
+                            self.warn(
+                                "`{` takes a condition but the stack is empty here, so it stops with an error".to_string(),
+                                &node.location,
+                            );
+                            return None;
+                        };
+                        if matches!(condition, Value::Nil | Value::Number(Some(0.0))) {
+                            return Some(state);
+                        }
+                        //once around, checking the condition again at the end
+                        let mut after = self.run(body, state.clone(), arguments)?;
+                        if self.pop(&mut after, arguments).is_none() {
+                            self.warn(
+                                "`{` checks its condition again but the stack is empty after the loop, so it stops with an error".to_string(),
+                                &node.location,
+                            );
+                            return None;
+                        }
+                        return merge(&state, &after);
+                    }
+                }
+            }
+            ASTnodeType::Container => unreachable!(), //containers are only ever the root
+        }
+        Some(state)
+    }
+    fn repeat(
+        &mut self,
+        body: &'a [ASTnode],
+        state: State<'a>,
+        count: Option<f64>,
+        arguments: bool,
+    ) -> Option<State<'a>> {
+        match count {
+            Some(count) if count <= 0.0 || count.fract() != 0.0 => Some(state), //a negative or fractional count is an error anyway
+            Some(count) if count <= MAX_UNROLL => {
+                let mut state = state;
+                for _ in 0..count as usize {
+                    let after = self.run(body, state.clone(), arguments)?;
+                    if after == state {
+                        break; //every other time around is the same
+                    }
+                    state = after;
+                }
+                Some(state)
+            }
+            //if going around once leaves the stacks the same size, going around any number of times does
+            _ => {
+                let after = self.run(body, state.clone(), arguments)?;
+                merge(&state, &after)
+            }
+        }
+    }
+    fn command(
+        &mut self,
+        node: &'a ASTnode,
+        mut state: State<'a>,
+        arguments: bool,
+    ) -> Option<State<'a>> {
+        let command = node.command.as_ref().unwrap();
+        let builtin = builtins::find(builtins::glyph(command)).unwrap();
+        let glyph = builtin.glyph;
+        match command {
+            Commands::DegreeCommand | Commands::RadianCommand => {
+                let (mode, full_circle) = match command {
+                    Commands::DegreeCommand => (TurnModes::DEGREE, 360.0),
+                    _ => (TurnModes::RADIAN, std::f64::consts::TAU),
+                };
+                if *state.mode.as_ref()? == mode {
+                    state.stack.push(Value::Number(Some(full_circle)));
+                } else {
+                    state.mode = Some(mode);
+                }
+                return Some(state);
+            }
+            Commands::ColorCommand => {
+                //three numbers, or one of anything else
+                let pops = match state.stack.last() {
+                    Some(Value::Number(_)) => 3,
+                    Some(Value::Unknown) => return None,
+                    Some(_) => 1,
+                    None if arguments => return None,
+                    None => 3,
+                };
+                return match self.take(node, builtin, pops, state, arguments) {
+                    Taken::Values(_, state) | Taken::Done(state) => Some(state),
+                };
+            }
+            Commands::ExecuteCommand => {
+                return match self.pop(&mut state, arguments) {
+                    Some(Value::Quotation(body)) if self.nesting < MAX_NESTING => {
+                        self.nesting += 1;
+                        let after = self.run(body, state, arguments);
+                        self.nesting -= 1;
+                        after
+                    }
+                    Some(Value::Quotation(_) | Value::Unknown) => None,
+                    Some(other) => {
+                        state.stack.push(other);
+                        Some(state)
+                    }
+                    None => {
+                        self.warn(format!("`{glyph}` takes 1 value but the stack is empty here, so it does nothing instead"), &node.location);
+                        Some(state)
+                    }
+                };
+            }
+            Commands::UndipCommand => {
+                let value = match state.dip.pop() {
+                    Some(value) => value,
+                    None if arguments => return None, //something its caller dipped
+                    None => {
+                        self.warn(
+                            format!("`{glyph}` takes 1 value from the dip stack but the dip stack is empty here, so it pushes 0 instead"),
+                            &node.location,
+                        );
+                        Value::Number(Some(0.0))
+                    }
+                };
+                state.stack.push(value);
+                return Some(state);
+            }
+            _ => {}
+        }
+        let effect = builtin.effect.unwrap(); //the rest always take and leave the same amount
+        let (args, mut state) = match self.take(node, builtin, effect.pops, state, arguments) {
+            Taken::Values(args, state) => (args, state),
+            Taken::Done(state) => return Some(state),
+        };
+        let pushed: Vec<Value<'a>> = match command {
+            Commands::DuplicateCommand => vec![args[0].clone(), args[0].clone()],
+            Commands::SwapCommand => vec![args[0].clone(), args[1].clone()],
+            Commands::RotCommand => vec![args[1].clone(), args[0].clone(), args[2].clone()],
+            Commands::UnrotCommand => vec![args[0].clone(), args[2].clone(), args[1].clone()],
+            Commands::DipCommand => {
+                state.dip.push(args[0].clone());
+                Vec::new()
+            }
+            Commands::BoxCommand => vec![Value::Box],
+            Commands::UnboxCommand => match &args[0] {
+                Value::Nil => vec![Value::Nil, Value::Nil, Value::Nil],
+                Value::Number(_) | Value::Quotation(_) => {
+                    vec![
+                        Value::Number(Some(0.0)),
+                        Value::Number(Some(0.0)),
+                        args[0].clone(),
+                    ]
+                }
+                Value::Box | Value::Unknown => vec![Value::Unknown, Value::Unknown, Value::Unknown],
+            },
+            Commands::IsBoxCommand => vec![match args[0] {
+                Value::Box => Value::Number(Some(1.0)),
+                Value::Unknown => Value::Number(None),
+                _ => Value::Number(Some(0.0)),
+            }],
+            Commands::IsNilCommand => vec![match args[0] {
+                Value::Nil => Value::Number(Some(1.0)),
+                Value::Number(_) | Value::Quotation(_) => Value::Number(Some(0.0)),
+                Value::Box | Value::Unknown => args[0].clone(),
+            }],
+            Commands::MatchCommand => vec![Value::Number(None)],
+            Commands::NilCommand => vec![Value::Nil],
+            Commands::EulerNumCommand => vec![Value::Number(Some(std::f64::consts::E))],
+            Commands::GetXCommand | Commands::GetYCommand | Commands::GetHeadingCommand => {
+                vec![Value::Number(None)]
+            }
+            command => match (dyadic(command), monadic(command)) {
+                (Some(f), _) => vec![math(|numbers| f(numbers[0], numbers[1]), &args)],
+                (_, Some(f)) => vec![math(|numbers| f(numbers[0]), &args)],
+                _ => Vec::new(), //drawing commands, which only take
+            },
+        };
+        //pushed has the bottom value first
+        state.stack.extend(pushed);
+        Some(state)
+    }
+    //pops a command's values, warning if the main code runs out and doing what its defaults say instead
+    fn take(
+        &mut self,
+        node: &ASTnode,
+        builtin: &builtins::Builtin,
+        pops: usize,
+        mut state: State<'a>,
+        arguments: bool,
+    ) -> Taken<'a> {
+        let mut args = Vec::new();
+        while args.len() < pops
+            && let Some(value) = self.pop(&mut state, arguments)
+        {
+            args.push(value);
+        }
+        if args.len() == pops {
+            return Taken::Values(args, state);
+        }
+        self.warn(
+            format!(
+                "{}, so {}",
+                builtins::running_out(builtin.glyph, pops, args.len()),
+                instead(builtin.defaults)
+            ),
+            &node.location,
+        );
+        match builtin.defaults {
+            Defaults::Fill(value) => {
+                args.resize(pops, Value::Number(Some(value)));
+                Taken::Values(args, state)
+            }
+            Defaults::Result(value) => {
+                state.stack.push(Value::Number(Some(value)));
+                Taken::Done(state)
+            }
+            _ => Taken::Done(state),
+        }
+    }
+}
+
+//what popping a command's values came to
+enum Taken<'a> {
+    Values(Vec<Value<'a>>, State<'a>), //top first
+    Done(State<'a>), //there weren't enough, so it did what its defaults say instead
+}
+
+#[cfg(test)]
+fn check_code(code: &str) -> Report {
+    crate::Interpreter::new().check("<test>", code).unwrap()
+}
+
+#[cfg(test)]
+fn main_leaves(code: &str) -> Option<usize> {
+    check_code(code).main.unwrap().map(|effect| effect.pushes)
+}
+
+#[test]
+fn effect_test() {
+    let report = check_code("#import list\nF_+\nG_.*1\n¤_:⊢\n1 2F G");
+    let effect = |pops, pushes| Some(StackEffect { pops, pushes });
+    //only the file's own functions, not the list library's
+    assert_eq!(
+        report.functions,
+        vec![
+            ('F', effect(2, 1)),
+            ('G', effect(1, 2)),
+            ('¤', effect(2, 2))
+        ]
+    );
+    assert_eq!(report.main, Some(effect(0, 2)));
+    assert_eq!(report.warnings, Vec::new());
+    assert_eq!(check_code("F_+").main, None);
+    //recursion can't be followed
+    assert_eq!(check_code("F_.[1:-F]\n3F").functions, vec![('F', None)]);
+}
+
+#[test]
+fn loop_test() {
+    assert_eq!(main_leaves("4[100^90~]"), Some(0));
+    assert_eq!(main_leaves("3[1]0[1 1]"), Some(3));
+    assert_eq!(main_leaves("1 x[2*]"), Some(1));
+    assert_eq!(main_leaves("x[1]"), None); //it could leave any number of 1s
+    assert_eq!(main_leaves("1 1{1-.}"), Some(1));
+    assert_eq!(main_leaves("\"1 2'`+"), Some(1));
+    //`o` pushes in degrees and switches back from radians
+    assert_eq!(main_leaves("o4/~"), Some(0));
+    assert_eq!(main_leaves("ro"), Some(0));
+    assert_eq!(main_leaves("0 0 255c 1 2 3B c"), Some(0));
+}
+
+#[test]
+fn warning_test() {
+    let warnings = |code| -> Vec<(String, usize)> {
+        check_code(code)
+            .warnings
+            .into_iter()
+            .map(|warning| (warning.message, warning.at.col))
+            .collect()
+    };
+    assert_eq!(
+        warnings("1+"),
+        vec![(
+            "`+` takes 2 values but only 1 is on the stack here, so the missing ones are filled in with 0".to_string(),
+            2
+        )]
+    );
+    assert_eq!(warnings("5[1.+]"), Vec::new());
+    assert_eq!(warnings("\"+'`").len(), 1);
+    assert_eq!(warnings("0 0 255c c")[0].1, 10);
+    assert_eq!(warnings("r o4/")[0].1, 5);
+    assert!(warnings(")")[0].0.contains("dip stack is empty"));
+    assert!(warnings("=")[0].0.ends_with("it leaves 0 instead"));
+    assert!(
+        warnings("F_+\n1F")[0]
+            .0
+            .starts_with("`F` takes 2 values but only 1 is on the stack here")
+    );
+    //inside a function, running out takes its arguments instead
+    assert_eq!(warnings("F_+\n1 2F"), Vec::new());
+    //a loop that is followed many times only warns once
+    assert_eq!(warnings("3[+1]").len(), 1);
+}
@@ -0,0 +1,242 @@
+//animated gif export, showing the drawing being built up a few segments at a time
+//frames are rasterized with the same canvas as png export and only the changed area of each frame is stored
+
+use crate::raster;
+use crate::turtle;
+use std::collections::HashMap;
+
+const MAX_CODES: u16 = 4096; //lzw codes are at most 12 bits
+
+struct Encoder {
+    out: Vec<u8>,
+    width: usize,
+    height: usize,
+    palette: Vec<[u8; 3]>,
+    lookup: HashMap<[u8; 3], u8>,
+    previous: Option<Vec<u8>>,
+}
+
+impl Encoder {
+    fn new(width: usize, height: usize, palette: Vec<[u8; 3]>) -> Self {
+        let mut out = b"GIF89a".to_vec();
+        out.extend((width as u16).to_le_bytes());
+        out.extend((height as u16).to_le_bytes());
+        out.extend([0xf7, 0, 0]); //256 color global table, background is index 0, square pixels
+        for i in 0..256 {
+            out.extend(palette.get(i).unwrap_or(&[0, 0, 0]));
+        }
+        //loop forever
+        out.extend([0x21, 0xff, 0x0b]);
+        out.extend(b"NETSCAPE2.0");
+        out.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+        Encoder {
+            out,
+            width,
+            height,
+            palette,
+            lookup: HashMap::new(),
+            previous: None,
+        }
+    }
+    fn index_of(&mut self, color: [u8; 3]) -> u8 {
+        if let Some(idx) = self.lookup.get(&color) {
+            return *idx;
+        }
+        //blended edges wont be in the palette, so use the closest color
+        let distance = |other: &[u8; 3]| -> i32 {
+            (0..3)
+                .map(|c| (color[c] as i32 - other[c] as i32).pow(2))
+                .sum()
+        };
+        let idx = (0..self.palette.len())
+            .min_by_key(|i| distance(&self.palette[*i]))
+            .unwrap_or(0) as u8;
+        self.lookup.insert(color, idx);
+        idx
+    }
+    //delay is in hundredths of a second
+    fn add_frame(&mut self, rgb: &[u8], delay: u16) {
+        let indices: Vec<u8> = rgb
+            .chunks(3)
+            .map(|pixel| self.index_of([pixel[0], pixel[1], pixel[2]]))
+            .collect();
+        //only store the rectangle that changed since the last frame
+        let (mut left, mut top, mut right, mut bottom) = (0, 0, self.width, self.height);
+        if let Some(previous) = &self.previous {
+            (left, top, right, bottom) = (self.width, self.height, 0, 0);
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if previous[y * self.width + x] != indices[y * self.width + x] {
+                        left = left.min(x);
+                        top = top.min(y);
+                        right = right.max(x + 1);
+                        bottom = bottom.max(y + 1);
+                    }
+                }
+            }
+            if left >= right {
+                //nothing changed, but the frame still needs to take up time
+                (left, top, right, bottom) = (0, 0, 1, 1);
+            }
+        }
+        //graphic control extension: leave the frame in place, no transparency
+        self.out.extend([0x21, 0xf9, 0x04, 0x04]);
+        self.out.extend(delay.to_le_bytes());
+        self.out.extend([0x00, 0x00]);
+        //image descriptor
+        self.out.push(0x2c);
+        for n in [left, top, right - left, bottom - top] {
+            self.out.extend((n as u16).to_le_bytes());
+        }
+        self.out.push(0x00);
+        let mut area = Vec::with_capacity((right - left) * (bottom - top));
+        for y in top..bottom {
+            area.extend(&indices[y * self.width + left..y * self.width + right]);
+        }
+        self.out.push(8); //minimum code size
+        for block in lzw(&area).chunks(255) {
+            self.out.push(block.len() as u8);
+            self.out.extend(block);
+        }
+        self.out.push(0x00);
+        self.previous = Some(indices);
+    }
+    fn finish(mut self) -> Vec<u8> {
+        self.out.push(0x3b);
+        self.out
+    }
+}
+
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear: u16 = 256;
+    let end: u16 = 257;
+    let mut out = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0u32);
+    let mut write = |code: u16, size: u32| {
+        acc |= (code as u32) << bits;
+        bits += size;
+        while bits >= 8 {
+            out.push(acc as u8);
+            acc >>= 8;
+            bits -= 8;
+        }
+    };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut code_size = 9;
+    write(clear, code_size);
+    let mut prefix: Option<u16> = None;
+    for index in indices {
+        let Some(current) = prefix else {
+            prefix = Some(*index as u16);
+            continue;
+        };
+        if let Some(code) = table.get(&(current, *index)) {
+            prefix = Some(*code);
+            continue;
+        }
+        write(current, code_size);
+        if next_code < MAX_CODES {
+            table.insert((current, *index), next_code);
+            next_code += 1;
+            //the decoder adds its entry one code later, so it widens when this passes the limit
+            if next_code > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            write(clear, code_size);
+            table.clear();
+            next_code = end + 1;
+            code_size = 9;
+        }
+        prefix = Some(*index as u16);
+    }
+    if let Some(current) = prefix {
+        write(current, code_size);
+    }
+    write(end, code_size);
+    if bits > 0 {
+        out.push(acc as u8);
+    }
+    out
+}
+
+pub fn from_turtle(
+    drawing_turtle: &turtle::Turtle,
+    canvas: raster::Canvas,
+    segments_per_frame: usize,
+    delay: u16,
+    hold: u16,
+) -> Vec<u8> {
+    let mut canvas = canvas;
+    let history = drawing_turtle.history();
+    let mut palette: Vec<[u8; 3]> = vec![[0, 0, 0]];
+    for frame in history {
+        let color = [frame.color.r, frame.color.g, frame.color.b];
+        if !palette.contains(&color) {
+            palette.push(color);
+        }
+    }
+    if palette.len() > 256 {
+        //too many pen colors for one table, so fall back to 3 bits of red and green and 2 of blue
+        palette = (0..=255u8)
+            .map(|i| [(i >> 5) * 255 / 7, ((i >> 2) & 7) * 255 / 7, (i & 3) * 255 / 3])
+            .collect();
+    }
+    let mut encoder = Encoder::new(canvas.width(), canvas.height(), palette);
+    let chunks: Vec<&[turtle::TurtleHistoryFrame]> =
+        history.chunks(segments_per_frame.max(1)).collect();
+    //start from an empty canvas so the loop is seamless
+    encoder.add_frame(&canvas.to_rgb(), if chunks.is_empty() { hold } else { delay });
+    for (i, chunk) in chunks.iter().enumerate() {
+        for frame in *chunk {
+            canvas.draw_frame(frame);
+        }
+        let is_last = i + 1 == chunks.len();
+        encoder.add_frame(&canvas.to_rgb(), if is_last { hold } else { delay });
+    }
+    encoder.finish()
+}
+
+#[test]
+fn lzw_test() {
+    //a long run has to grow the code size past 9 bits, and a varied one has to fill the table and clear it
+    let run = vec![0u8; 100000];
+    assert!(lzw(&run).len() < 2000);
+    let varied: Vec<u8> = (0..100000u32).map(|i| (i * 7919 % 251) as u8).collect();
+    let packed = lzw(&varied);
+    assert_eq!(packed[0] as u16 | ((packed[1] as u16 & 1) << 8), 256); //starts with a clear code
+}
+
+#[test]
+fn from_turtle_test() {
+    let mut t = turtle::Turtle::new();
+    for _ in 0..4 {
+        t.forward(100.0);
+        t.push();
+        t.turn(90.0);
+    }
+    let gif = from_turtle(&t, raster::Canvas::new(80, 45, 1), 2, 5, 100);
+    assert_eq!(&gif[..6], b"GIF89a");
+    //walk the blocks after the header and color table, collecting each frame's delay
+    let mut delays = Vec::new();
+    let mut idx = 13 + 256 * 3;
+    let skip_sub_blocks = |mut idx: usize| {
+        while gif[idx] != 0 {
+            idx += gif[idx] as usize + 1;
+        }
+        idx + 1
+    };
+    while gif[idx] != 0x3b {
+        if gif[idx] == 0x21 {
+            if gif[idx + 1] == 0xf9 {
+                delays.push(u16::from_le_bytes([gif[idx + 4], gif[idx + 5]]));
+            }
+            idx = skip_sub_blocks(idx + 2);
+        } else {
+            assert_eq!(gif[idx], 0x2c);
+            idx = skip_sub_blocks(idx + 11);
+        }
+    }
+    assert_eq!(delays, vec![5, 5, 100]); //empty, half done, done
+}
@@ -0,0 +1,1429 @@
+//runs drw code. everything the command line does besides reading files and showing the drawing is here,
+//so drw can be used from other rust code too
+
+use crate::ast;
+use crate::builtins;
+use crate::bytecode;
+use crate::check;
+use crate::item;
+use crate::modules;
+use crate::stack;
+use crate::turtle;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+const THE_NUMBER_OF_RADIANS_IN_A_CIRCLE: f64 = std::f64::consts::TAU;
+const EULERS_NUMBER: f64 = std::f64::consts::E;
+const THE_NUMBER_OF_DEGREES_IN_A_CIRCLE: f64 = 360.0;
+//calls and quotations that haven't returned yet. tail calls don't count, since they return straight to their caller's caller
+const MAX_CALL_DEPTH: usize = 1_000_000;
+
+enum Line {
+    Function(char, String, bool), //the bool is for `__`, which replaces an earlier definition on purpose
+    Code(String),
+}
+
+//points a module error at the `#import` line it came from. errors from inside the module already say where they are
+fn import_error(err: Error, at: ast::Location, source: &str) -> Error {
+    match err {
+        Error::Module(message) => {
+            Error::Parse(ast::ParseError::new(message, at, None).with_source(source))
+        }
+        err => err,
+    }
+}
+
+//a function's body and where it starts, so errors can point into the right file
+struct FunctionSource {
+    body: String,
+    start: ast::Location,
+}
+
+//strips the comment off a line and works out if it defines a function
+fn classify_line(line: &str) -> Line {
+    let no_comment: Vec<char> = line.chars().take_while(|c| *c != '#').collect();
+    if no_comment.len() >= 2 && no_comment[1] == '_' {
+        let replace = no_comment.get(2) == Some(&'_');
+        let body_idx = if replace { 3 } else { 2 };
+        Line::Function(no_comment[0], no_comment[body_idx..].iter().collect(), replace)
+    } else {
+        Line::Code(no_comment.into_iter().collect())
+    }
+}
+
+//a function named after a builtin could never be called, since the builtin is looked up first
+fn is_builtin(glyph: char) -> bool {
+    builtins::find(glyph).is_some() || builtins::find_bracket(glyph).is_some()
+}
+
+//function bodies start after the name and the `_` (or `__`)
+fn body_start(file: &Rc<str>, line_idx: usize, replace: bool) -> ast::Location {
+    ast::Location::new(file.clone(), line_idx + 1, if replace { 4 } else { 3 })
+}
+
+//an error while running, with where it happened and what the stack looked like then
+#[derive(Clone, PartialEq, Debug)]
+pub struct RuntimeError {
+    pub message: String,
+    pub at: ast::Location,
+    pub stack: String, //printed the same way `?` prints it
+    source_line: Option<String>,
+}
+
+impl RuntimeError {
+    pub fn new(
+        message: String,
+        at: &ast::Location,
+        data_stack: &stack::Stack<item::Item>,
+    ) -> Self {
+        RuntimeError {
+            message,
+            at: at.clone(),
+            stack: data_stack.to_string(),
+            source_line: None,
+        }
+    }
+    pub fn with_source(mut self, sources: &HashMap<String, String>) -> Self {
+        self.source_line = sources
+            .get(&*self.at.file)
+            .and_then(|text| text.lines().nth(self.at.line - 1))
+            .map(str::to_string);
+        self
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error: {}", self.message)?;
+        write!(f, " --> {}", self.at)?;
+        if self.source_line.is_some() {
+            writeln!(f)?;
+            ast::write_snippet(f, &self.at, self.source_line.as_deref(), "")?;
+        }
+        write!(f, "\nstack when it happened:\n{}", self.stack)
+    }
+}
+
+//runs one of the commands that only touch the stacks and the turtle
+#[allow(unreachable_patterns)]
+fn command(
+    command: &ast::Commands,
+    data_stack: &mut stack::Stack<item::Item>,
+    dip_stack: &mut stack::Stack<item::Item>,
+    drawing_turtle: &mut turtle::Turtle,
+) {
+    match command {
+        ast::Commands::ForwardCommand => {
+            //nil, quotations and an empty stack don't move the turtle at all
+            if let Some(distance) = data_stack.pop().and_then(|item| item.as_number()) {
+                drawing_turtle.forward(distance as f32);
+                drawing_turtle.push();
+            }
+        }
+        ast::Commands::TurnCommand => drawing_turtle.turn(
+            data_stack
+                .pop()
+                .unwrap_or(item::Item::zero())
+                .get_number_or(0.0) as f32,
+        ),
+        ast::Commands::DuplicateCommand => data_stack.dup(item::Item::zero()),
+        ast::Commands::SwapCommand => data_stack.swap(item::Item::zero()),
+        ast::Commands::PopCommand => {
+            let _throwaway = data_stack.pop();
+        }
+        ast::Commands::RotCommand => {
+            //a b c -> c a b
+            let a = data_stack.pop().unwrap_or(item::Item::zero());
+            let b = data_stack.pop().unwrap_or(item::Item::zero());
+            let c = data_stack.pop().unwrap_or(item::Item::zero());
+
+            data_stack.push(b);
+            data_stack.push(a);
+            data_stack.push(c);
+        }
+        ast::Commands::UnrotCommand => {
+            //a b c -> b c a
+            let a = data_stack.pop().unwrap_or(item::Item::zero());
+            let b = data_stack.pop().unwrap_or(item::Item::zero());
+            let c = data_stack.pop().unwrap_or(item::Item::zero());
+
+            data_stack.push(a);
+            data_stack.push(c);
+            data_stack.push(b);
+        }
+
+        ast::Commands::AddCommand => dyadic_op(&|a, b| a + b, 0.0, data_stack),
+        ast::Commands::SubtractCommand => dyadic_op(&|a, b| a - b, 0.0, data_stack),
+        ast::Commands::MultiplyCommand => dyadic_op(&|a, b| a * b, 1.0, data_stack),
+        ast::Commands::DivideCommand => dyadic_op(&|a, b| a / b, 1.0, data_stack),
+        ast::Commands::ModuloCommand => dyadic_op(&|a, b| a % b, 1.0, data_stack),
+        ast::Commands::DegreeCommand => {
+            if drawing_turtle.using_degrees() {
+                data_stack.push(item::Item::from_num(THE_NUMBER_OF_DEGREES_IN_A_CIRCLE));
+            } else {
+                drawing_turtle.set_turn_mode(turtle::TurnModes::DEGREE);
+            }
+        }
+        ast::Commands::RadianCommand => {
+            if drawing_turtle.using_radians() {
+                data_stack.push(item::Item::from_num(THE_NUMBER_OF_RADIANS_IN_A_CIRCLE));
+            } else {
+                drawing_turtle.set_turn_mode(turtle::TurnModes::RADIAN);
+            }
+        }
+        ast::Commands::ColorCommand => {
+            let channels = if data_stack.peek().unwrap_or(&item::Item::zero()).itemtype
+                == item::ItemType::Number
+            {
+                let mut channel = || data_stack.pop().unwrap_or(item::Item::from_num(255.0));
+                [channel(), channel(), channel()]
+            } else {
+                //unwrap is fine because empty stack was checked for in isnum check
+                let item = data_stack.pop().unwrap();
+                if item.itemtype != item::ItemType::Box {
+                    return; //nil and quotations are ignored
+                }
+                let the_box = item.get_box();
+                [the_box.r, the_box.g, the_box.b]
+            };
+            //a nil or a quotation in any channel means there is no color to set
+            if let [Some(r), Some(g), Some(b)] = channels.map(|channel| channel.as_number()) {
+                drawing_turtle.set_color(r as u8, g as u8, b as u8);
+            }
+        }
+        ast::Commands::PenDownCommand => drawing_turtle.pen_down(),
+        ast::Commands::PenUpCommand => drawing_turtle.pen_up(),
+        ast::Commands::SizeCommand => {
+            //like forward, nil and quotations are ignored
+            if let Some(size) = data_stack.pop().and_then(|item| item.as_number()) {
+                drawing_turtle.set_pen_size(size as f32);
+            }
+        }
+        ast::Commands::PowerCommand => dyadic_op(&|a, b| a.powf(b), 1.0, data_stack),
+        ast::Commands::LogCommand => dyadic_op(&|a, b| a.log(b), EULERS_NUMBER, data_stack),
+        ast::Commands::EulerNumCommand => {
+            data_stack.push(item::Item::from_num(EULERS_NUMBER))
+        }
+        ast::Commands::SquareRootCommand => monadic_op(&|a| a.sqrt(), 1.0, data_stack),
+        ast::Commands::SineCommand => monadic_op(&|a| a.sin(), 0.0, data_stack),
+        ast::Commands::CeilingCommand => monadic_op(&|a| a.ceil(), 0.0, data_stack),
+        ast::Commands::FloorCommand => monadic_op(&|a| a.floor(), 0.0, data_stack),
+        ast::Commands::RoundCommand => monadic_op(&|a| a.round(), 1.0, data_stack),
+        ast::Commands::LessThanCommand => comp_op(&|a, b| a < b, data_stack),
+        ast::Commands::GreaterThanCommand => comp_op(&|a, b| a > b, data_stack),
+        ast::Commands::EqualCommand => comp_op(&|a, b| a == b, data_stack),
+        ast::Commands::DipCommand => data_stack.dip(dip_stack, item::Item::zero()),
+        ast::Commands::UndipCommand => dip_stack.dip(data_stack, item::Item::zero()),
+        ast::Commands::BoxCommand => {
+            let r = data_stack.pop().unwrap_or(item::Item::zero());
+            let g = data_stack.pop().unwrap_or(item::Item::zero());
+            let b = data_stack.pop().unwrap_or(item::Item::zero());
+            data_stack.push(item::Item::from_box(item::DrwBox::new(r, g, b)));
+        }
+        ast::Commands::UnboxCommand => {
+            if data_stack.peek().unwrap_or(&item::Item::zero()).itemtype
+                != item::ItemType::Nil
+            {
+                let the_box = data_stack
+                    .pop()
+                    .unwrap_or(item::Item::from_box(item::DrwBox::from_nums(0.0, 0.0, 0.0)))
+                    .get_box();
+                data_stack.push(the_box.b);
+                data_stack.push(the_box.g);
+                data_stack.push(the_box.r);
+            } else {
+                //to help reduce nil checks in code
+                data_stack.push(item::Item::nil());
+                data_stack.push(item::Item::nil());
+            }
+        }
+        ast::Commands::IsBoxCommand => {
+            let is_box = if let Some(item) = data_stack.pop()
+                && item.itemtype == item::ItemType::Box
+            {
+                1.0
+            } else {
+                0.0
+            };
+            data_stack.push(item::Item::from_num(is_box))
+        }
+        ast::Commands::NilCommand => data_stack.push(item::Item::nil()),
+        ast::Commands::IsNilCommand => {
+            let a = data_stack.pop().unwrap_or(item::Item::zero());
+            data_stack.push(apply_is_nil(a));
+        }
+        ast::Commands::MatchCommand => {
+            if let Some(a) = data_stack.pop() {
+                if let Some(b) = data_stack.pop() {
+                    data_stack.push(item::Item::from_num(if a == b { 1.0 } else { 0.0 }));
+                }else{
+			    data_stack.push(item::Item::zero());
+			}
+            } else {
+                data_stack.push(item::Item::zero());
+            }
+        }
+        ast::Commands::GetXCommand => {
+            data_stack.push(item::Item::from_num(drawing_turtle.position().x as f64))
+        }
+        ast::Commands::GetYCommand => {
+            data_stack.push(item::Item::from_num(drawing_turtle.position().y as f64))
+        }
+        ast::Commands::SetXCommand => {
+            if let Some(x) = pop_coordinate(data_stack) {
+                let y = drawing_turtle.position().y;
+                drawing_turtle.jump(turtle::Point::new(x, y));
+            }
+        }
+        ast::Commands::SetYCommand => {
+            if let Some(y) = pop_coordinate(data_stack) {
+                let x = drawing_turtle.position().x;
+                drawing_turtle.jump(turtle::Point::new(x, y));
+            }
+        }
+        ast::Commands::GetHeadingCommand => {
+            data_stack.push(item::Item::from_num(drawing_turtle.heading() as f64))
+        }
+        ast::Commands::SetHeadingCommand => {
+            if let Some(angle) = pop_coordinate(data_stack) {
+                drawing_turtle.set_heading(angle);
+            }
+        }
+        ast::Commands::BeginFillCommand => drawing_turtle.begin_fill(),
+        ast::Commands::EndFillCommand => drawing_turtle.end_fill(turtle::FillRule::NonZero),
+        ast::Commands::EndEvenOddFillCommand => {
+            drawing_turtle.end_fill(turtle::FillRule::EvenOdd)
+        }
+        _ => unreachable!(), //execute and `?` are run by execute
+    }
+}
+
+//what --strict stops on before a command runs: running out of stack, where the command would fill in a default,
+//and nil or a quotation reaching a drawing command, which would ignore it
+fn strict_check(
+    command: &ast::Commands,
+    data_stack: &stack::Stack<item::Item>,
+    dip_stack: &stack::Stack<item::Item>,
+) -> Result<(), String> {
+    let glyph = builtins::glyph(command);
+    let items = data_stack.items();
+    let top = items.last();
+    let takes = match command {
+        ast::Commands::UndipCommand if dip_stack.items().is_empty() => {
+            return Err(format!("`{glyph}` takes 1 value from the dip stack but the dip stack is empty here"));
+        }
+        ast::Commands::ColorCommand
+            if top.is_none_or(|item| item.itemtype == item::ItemType::Number) =>
+        {
+            3
+        }
+        ast::Commands::ColorCommand => 1,
+        _ => builtins::find(glyph).unwrap().effect.map_or(0, |effect| effect.pops),
+    };
+    if items.len() < takes {
+        return Err(builtins::running_out(glyph, takes, items.len()));
+    }
+    let given = match command {
+        ast::Commands::ForwardCommand
+        | ast::Commands::SizeCommand
+        | ast::Commands::SetXCommand
+        | ast::Commands::SetYCommand
+        | ast::Commands::SetHeadingCommand => &items[items.len() - 1..],
+        ast::Commands::ColorCommand => &items[items.len() - takes..],
+        _ => &[],
+    };
+    for item in given.iter().rev() {
+        let numbers = match &item.boxed {
+            //a box is a whole color for `c`, and its red channel for everything else
+            Some(the_box) if *command == ast::Commands::ColorCommand => {
+                [&the_box.r, &the_box.g, &the_box.b].map(|channel| channel.as_number())
+            }
+            _ => [item.as_number(); 3],
+        };
+        if numbers.contains(&None) {
+            return Err(format!("`{glyph}` was given `{item}`, which it would ignore"));
+        }
+    }
+    Ok(())
+}
+
+//runs compiled code from start until it halts. calls and loops keep their state in vecs here instead of on rust's stack
+fn execute(
+    program: &bytecode::Program,
+    start: usize,
+    data_stack: &mut stack::Stack<item::Item>,
+    dip_stack: &mut stack::Stack<item::Item>,
+    drawing_turtle: &mut turtle::Turtle,
+    run: &mut Run,
+) -> Result<(), RuntimeError> {
+    let mut pc = start;
+    let mut returns: Vec<usize> = Vec::new(); //where each call goes back to
+    let mut counts: Vec<u64> = Vec::new(); //how many more times each repeat loop has to go
+    let mut depth = 0; //how many loops, calls and quotations deep this is, for --trace
+    loop {
+        let at = pc;
+        pc += 1;
+        let origin = &program.origins[at];
+        match &program.code[at] {
+            bytecode::Instruction::Push(number) => {
+                run.step(origin, data_stack)?;
+                data_stack.push(item::Item::from_num(*number));
+            }
+            bytecode::Instruction::Command(ast::Commands::DebugCommand) => {
+                run.step(origin, data_stack)?;
+                run.print(data_stack);
+            }
+            bytecode::Instruction::Command(c) => {
+                run.step(origin, data_stack)?;
+                if run.strict {
+                    strict_check(c, data_stack, dip_stack)
+                        .map_err(|message| RuntimeError::new(message, &origin.location, data_stack))?;
+                }
+                command(c, data_stack, dip_stack, drawing_turtle);
+            }
+            bytecode::Instruction::PushQuotation(quotation, after) => {
+                run.step(origin, data_stack)?;
+                data_stack.push(item::Item::from_quotation(quotation.clone()));
+                pc = *after;
+            }
+            bytecode::Instruction::Execute => {
+                run.step(origin, data_stack)?;
+                run.check_not_empty('`', origin, data_stack)?;
+                match data_stack.pop() {
+                    Some(quotation) if quotation.itemtype == item::ItemType::Quotation => {
+                        if run.trace {
+                            trace_step(origin, depth, data_stack, dip_stack);
+                        }
+                        call(program, &mut pc, &mut returns, &mut counts, &mut depth, origin, data_stack)?;
+                        pc = quotation.quoted.as_ref().unwrap().start;
+                        continue; //already traced
+                    }
+                    //executing anything else leaves it alone
+                    Some(other) => data_stack.push(other),
+                    None => {}
+                }
+            }
+            bytecode::Instruction::Call(idx) => {
+                run.step(origin, data_stack)?;
+                let Some(function_start) = program.functions[*idx] else {
+                    return Err(RuntimeError::new(
+                        format!("{} is not defined", origin.text()),
+                        &origin.location,
+                        data_stack,
+                    ));
+                };
+                if run.trace {
+                    trace_step(origin, depth, data_stack, dip_stack);
+                }
+                call(program, &mut pc, &mut returns, &mut counts, &mut depth, origin, data_stack)?;
+                pc = function_start;
+                continue; //already traced
+            }
+            bytecode::Instruction::Return => {
+                pc = returns.pop().unwrap();
+                depth -= 1;
+                continue;
+            }
+            bytecode::Instruction::RepeatStart(after) => {
+                run.step(origin, data_stack)?;
+                run.check_not_empty('[', origin, data_stack)?;
+                let n = data_stack.pop().unwrap_or(item::Item::zero());
+                if n.itemtype == item::ItemType::Box {
+                    return Err(RuntimeError::new(
+                        format!("Cannot iterate over boxes! Got: {n}"),
+                        &origin.location,
+                        data_stack,
+                    ));
+                }
+                if n.itemtype == item::ItemType::Quotation {
+                    return Err(RuntimeError::new(
+                        format!("Cannot iterate over quotations! Got: {n}"),
+                        &origin.location,
+                        data_stack,
+                    ));
+                }
+                let num: f64 = n.get_number_or(0.0);
+                if num != num.floor() {
+                    return Err(RuntimeError::new(
+                        format!("Cannot iterate a non-whole number of times! Got: {num}"),
+                        &origin.location,
+                        data_stack,
+                    ));
+                }
+                if num < 0.0 {
+                    return Err(RuntimeError::new(
+                        format!(
+                            "Cannot iterate a negative number of times! (drw isn't Uiua) Got: {num}"
+                        ),
+                        &origin.location,
+                        data_stack,
+                    ));
+                }
+                if run.trace {
+                    trace_step(origin, depth, data_stack, dip_stack);
+                }
+                if num == 0.0 {
+                    pc = *after;
+                } else {
+                    counts.push(num as u64);
+                    depth += 1;
+                }
+                continue; //loops are traced when they start, so their insides come after them
+            }
+            bytecode::Instruction::RepeatEnd(body) => {
+                run.step(origin, data_stack)?; //so even an empty loop can't go on forever
+                let count = counts.last_mut().unwrap();
+                *count -= 1;
+                if *count > 0 {
+                    pc = *body;
+                } else {
+                    counts.pop();
+                    depth -= 1;
+                }
+                continue;
+            }
+            bytecode::Instruction::WhileStart(after) => {
+                run.step(origin, data_stack)?;
+                let Some(condition) = data_stack.pop() else {
+                    return Err(RuntimeError::new(
+                        "The stack ran out while checking a while loop's condition".to_string(),
+                        &origin.location,
+                        data_stack,
+                    ));
+                };
+                if !condition.is_truthy() {
+                    pc = *after;
+                    continue;
+                }
+                if run.trace {
+                    //once per time around, since each one checks the condition again
+                    trace_step(origin, depth, data_stack, dip_stack);
+                }
+                depth += 1;
+                continue;
+            }
+            bytecode::Instruction::WhileEnd(condition) => {
+                pc = *condition;
+                depth -= 1;
+                continue;
+            }
+            bytecode::Instruction::Halt => return Ok(()),
+        }
+        if run.trace {
+            trace_step(origin, depth, data_stack, dip_stack);
+        }
+    }
+}
+
+//remembers where to go back to before a call or `` ` `` jumps away. when all that is left after it is a return,
+//the callee returns straight to where that return would have gone, so tail recursion doesn't use up any depth.
+//that includes ending repeat loops on their last time around, so `F_.0>[1:-F]` is a tail call too
+fn call(
+    program: &bytecode::Program,
+    pc: &mut usize,
+    returns: &mut Vec<usize>,
+    counts: &mut Vec<u64>,
+    depth: &mut usize,
+    origin: &bytecode::Origin,
+    data_stack: &mut stack::Stack<item::Item>,
+) -> Result<(), RuntimeError> {
+    let mut after = *pc;
+    let mut finished_loops = 0;
+    while matches!(program.code[after], bytecode::Instruction::RepeatEnd(_))
+        && counts.len() > finished_loops
+        && counts[counts.len() - 1 - finished_loops] == 1
+    {
+        after += 1;
+        finished_loops += 1;
+    }
+    if matches!(program.code[after], bytecode::Instruction::Return) {
+        counts.truncate(counts.len() - finished_loops);
+        *depth -= finished_loops;
+        return Ok(());
+    }
+    if returns.len() >= MAX_CALL_DEPTH {
+        return Err(RuntimeError::new(
+            format!("Recursed more than {MAX_CALL_DEPTH} calls deep, the most allowed"),
+            &origin.location,
+            data_stack,
+        ));
+    }
+    returns.push(*pc);
+    *depth += 1;
+    Ok(())
+}
+
+//what lasts for one whole run: tracing and the limits on how long it can go
+struct Run {
+    trace: bool,
+    steps: u64,
+    max_steps: Option<u64>,
+    deadline: Option<(Instant, Duration)>, //when to stop, and the timeout it came from for the error
+    printed: Option<String>, //what `?` printed, when it is kept instead of going to stdout
+    strict: bool,
+}
+
+impl Run {
+    //counts one step, stopping the run if it has gone on for too long
+    fn step(
+        &mut self,
+        origin: &bytecode::Origin,
+        data_stack: &mut stack::Stack<item::Item>,
+    ) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        if let Some(max_steps) = self.max_steps
+            && self.steps > max_steps
+        {
+            return Err(RuntimeError::new(
+                format!("Stopped after running {max_steps} steps, the most allowed"),
+                &origin.location,
+                data_stack,
+            ));
+        }
+        //checking the clock every step would slow everything down
+        if let Some((deadline, timeout)) = self.deadline
+            && self.steps.is_multiple_of(1024)
+            && Instant::now() >= deadline
+        {
+            return Err(RuntimeError::new(
+                format!("Stopped after running for {} seconds, the most allowed", timeout.as_secs_f64()),
+                &origin.location,
+                data_stack,
+            ));
+        }
+        Ok(())
+    }
+    //in --strict, for the things that aren't commands but still pop
+    fn check_not_empty(
+        &self,
+        glyph: char,
+        origin: &bytecode::Origin,
+        data_stack: &mut stack::Stack<item::Item>,
+    ) -> Result<(), RuntimeError> {
+        if self.strict && data_stack.items().is_empty() {
+            return Err(RuntimeError::new(builtins::running_out(glyph, 1, 0), &origin.location, data_stack));
+        }
+        Ok(())
+    }
+    fn print(&mut self, data_stack: &stack::Stack<item::Item>) {
+        match &mut self.printed {
+            Some(printed) => {
+                *printed += &data_stack.to_string();
+                printed.push('\n');
+            }
+            None => println!("{data_stack}"),
+        }
+    }
+}
+
+//one line of --trace output: where the step is, what it was, and both stacks after it, bottom first
+fn trace_step(
+    origin: &bytecode::Origin,
+    depth: usize,
+    data_stack: &stack::Stack<item::Item>,
+    dip_stack: &stack::Stack<item::Item>,
+) {
+    let show = |items: &[item::Item]| {
+        items.iter().map(|item| item.to_string()).collect::<Vec<String>>().join(" ")
+    };
+    let step = "  ".repeat(depth) + origin.text();
+    let line = format!(
+        "{}\t{:<16} stack: {} | dip: {}",
+        origin.location,
+        step,
+        show(data_stack.items()),
+        show(dip_stack.items()),
+    );
+    eprintln!("{}", line.trim_end());
+}
+
+//a drw program's functions, stacks and turtle, which last between runs
+pub struct Interpreter {
+    functions: HashMap<char, FunctionSource>,
+    program: bytecode::Program,
+    compiled: HashSet<char>, //functions whose latest definition is in the program
+    sources: HashMap<String, String>, //the text of everything loaded, by name, for error messages
+    data_stack: stack::Stack<item::Item>,
+    dip_stack: stack::Stack<item::Item>,
+    drawing_turtle: turtle::Turtle,
+    trace: bool,
+    max_steps: Option<u64>,
+    timeout: Option<Duration>,
+    printed: Option<String>, //see keep_printed
+    strict: bool,
+    search_path: Vec<PathBuf>, //where to look for imported modules, after the importing file's folder
+    folders: HashMap<String, PathBuf>, //the real folders of files run by their name alone, see set_folder
+    imported: HashSet<String>, //modules already loaded, by their key, so importing one twice does nothing
+    importing: Vec<(String, String)>, //the keys and names of modules being loaded right now, innermost last, to catch circular imports
+}
+
+//anything that can stop a run. both kinds quote the line they happened on
+#[derive(Clone, PartialEq, Debug)]
+pub enum Error {
+    Parse(ast::ParseError),
+    Runtime(RuntimeError),
+    Module(String), //a module that couldn't be found or was imported in a circle, from outside any file
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(err) => write!(f, "{err}"),
+            Error::Runtime(err) => write!(f, "{err}"),
+            Error::Module(message) => write!(f, "error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter {
+            functions: HashMap::new(),
+            program: bytecode::Program::new(),
+            compiled: HashSet::new(),
+            sources: HashMap::new(),
+            data_stack: stack::Stack::new(),
+            dip_stack: stack::Stack::new(),
+            drawing_turtle: turtle::Turtle::new(),
+            trace: false,
+            max_steps: None,
+            timeout: None,
+            printed: None,
+            strict: false,
+            search_path: modules::search_path_from_env(),
+            folders: HashMap::new(),
+            imported: HashSet::new(),
+            importing: Vec::new(),
+        }
+    }
+    //prints every step to stderr as it runs, see trace_step
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+    //stops each run with an error once it has run this many steps. loops count each time around too
+    pub fn set_max_steps(&mut self, max_steps: Option<u64>) {
+        self.max_steps = max_steps;
+    }
+    //stops each run with an error once it has gone on for this long. whatever was drawn is kept either way
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+    //stops with an error where the stack would run out and a default would be filled in, or where a drawing
+    //command would ignore nil, instead of carrying on
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+    //keeps what `?` prints for take_printed instead of writing it to stdout
+    pub fn keep_printed(&mut self) {
+        self.printed.get_or_insert_with(String::new);
+    }
+    //everything `?` has printed since the last take, if keep_printed was called
+    pub fn take_printed(&mut self) -> String {
+        self.printed.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+    //where imported modules are looked for after the importing file's folder. DRW_PATH by default
+    pub fn set_search_path(&mut self, search_path: Vec<PathBuf>) {
+        self.search_path = search_path;
+    }
+    //a file run by its name alone has its `#import` lines looked for in dir instead of the current folder
+    pub fn set_folder(&mut self, name: &str, dir: &Path) {
+        self.folders.insert(name.to_string(), dir.to_path_buf());
+    }
+    //loads a module by path or by name like `#import` does, looking from the current folder first
+    pub fn import(&mut self, name: &str) -> Result<(), Error> {
+        self.import_from(name, Path::new(""), &[])
+    }
+    //loads the functions in a module. they can use functions that are only defined later, so they are checked when something runs
+    pub fn add_module(&mut self, name: &str, source: &str) -> Result<(), Error> {
+        self.add_renamed_module(name, source, &[])
+    }
+    //like add_module, but the functions named in renames are defined (and called by the module) under their new names
+    fn add_renamed_module(&mut self, name: &str, source: &str, renames: &[(char, char)]) -> Result<(), Error> {
+        self.imports(name, source)?;
+        let defines = |function: char| {
+            source.lines().any(|line| matches!(classify_line(line), Line::Function(name, ..) if name == function))
+        };
+        if let Some((old, _)) = renames.iter().find(|(old, _)| !defines(*old)) {
+            return Err(Error::Module(format!("{name} doesn't define `{old}`, so it can't be renamed")));
+        }
+        if let Some((old, new)) = renames.iter().find(|(_, new)| is_builtin(*new)) {
+            return Err(Error::Module(format!("`{old}` can't be renamed to `{new}`, which is a builtin")));
+        }
+        let rename = |c: char| renames.iter().find(|(old, _)| *old == c).map_or(c, |(_, new)| *new);
+        let file: Rc<str> = Rc::from(name);
+        for (line_idx, line) in source.lines().enumerate() {
+            match classify_line(line) {
+                Line::Function(function_name, body, replace) => {
+                    let start = body_start(&file, line_idx, replace);
+                    let body = body.chars().map(rename).collect();
+                    self.define(rename(function_name), body, start, replace, source)?;
+                }
+                Line::Code(code) if code.chars().count() == 1 => {
+                    return Err(Error::Parse(
+                        ast::ParseError::new(
+                            format!(
+                                "Modules must be all functions and all functions are delinated by a name and a binding (`_`). `{code}` does not contain a binding"
+                            ),
+                            ast::Location::new(file, line_idx + 1, 1),
+                            None,
+                        )
+                        .with_source(source),
+                    ));
+                }
+                Line::Code(_) => continue,
+            }
+        }
+        self.sources.insert(name.to_string(), source.to_string());
+        Ok(())
+    }
+    //runs a whole program: its function lines are defined first, then every other line runs in order
+    pub fn run(&mut self, name: &str, source: &str) -> Result<(), Error> {
+        let main_ast = self.load(name, source)?;
+        self.eval(main_ast)
+    }
+    //works out what a program's functions and main code do to the stack without running them, see check.rs
+    pub fn check(&mut self, name: &str, source: &str) -> Result<check::Report, Error> {
+        let main_ast = self.load(name, source)?;
+        let function_names = self.function_names();
+        let mut functions = HashMap::new();
+        for (function_name, function) in &self.functions {
+            let ast = ast::AST::new_at(function.body.clone(), function_names.clone(), function.start.clone())
+                .map_err(|err| Error::Parse(self.with_source(err)))?;
+            functions.insert(*function_name, ast);
+        }
+        //only the program's own functions are reported on, not the ones it imported
+        let mut listed: Vec<(&char, &FunctionSource)> =
+            self.functions.iter().filter(|(_, function)| *function.start.file == *name).collect();
+        listed.sort_by_key(|(_, function)| function.start.line);
+        let listed: Vec<char> = listed.into_iter().map(|(function_name, _)| *function_name).collect();
+        let mut report = check::check(&main_ast, &functions, &listed);
+        report.warnings = report
+            .warnings
+            .into_iter()
+            .map(|warning| match self.sources.get(&*warning.at.file) {
+                Some(text) => warning.with_source(text),
+                None => warning,
+            })
+            .collect();
+        Ok(report)
+    }
+    //defines a program's functions and parses the rest of it, ready to run
+    fn load(&mut self, name: &str, source: &str) -> Result<ast::AST, Error> {
+        self.imports(name, source)?;
+        let file: Rc<str> = Rc::from(name);
+        //function lines are left blank so the main code keeps its line numbers
+        let mut non_function: Vec<String> = Vec::new();
+        for (line_idx, line) in source.lines().enumerate() {
+            match classify_line(line) {
+                Line::Function(function_name, body, replace) => {
+                    let start = body_start(&file, line_idx, replace);
+                    self.define(function_name, body, start, replace, source)?;
+                    non_function.push(String::new());
+                }
+                Line::Code(code) => non_function.push(code),
+            }
+        }
+        self.sources.insert(name.to_string(), source.to_string());
+        self.build_functions()
+            .and_then(|_| {
+                ast::AST::new_at(
+                    non_function.join("\n"),
+                    self.function_names(),
+                    ast::Location::start_of(name),
+                )
+            })
+            .map_err(|err| Error::Parse(self.with_source(err)))
+    }
+    //runs one more line of a session, like the repl does. `name` is the session's name in error messages,
+    //which quote any line entered so far
+    pub fn run_line(&mut self, name: &str, line: &str) -> Result<(), Error> {
+        let session = self.sources.entry(name.to_string()).or_default();
+        let line_idx = session.lines().count();
+        *session += line;
+        session.push('\n');
+        let file: Rc<str> = Rc::from(name);
+        if let Some(import) = modules::parse_import(line) {
+            let at = ast::Location::new(file, line_idx + 1, 1);
+            return self
+                .import_from(import.name, Path::new(""), &import.renames)
+                .map_err(|err| import_error(err, at, line));
+        }
+        match classify_line(line) {
+            Line::Function(function_name, body, replace) => {
+                let mut function_names = self.function_names();
+                if !function_names.contains(&function_name) {
+                    function_names.push(function_name); //so it can call itself
+                }
+                let start = body_start(&file, line_idx, replace);
+                let ast = ast::AST::new_at(body.clone(), function_names, start.clone())
+                    .map_err(|err| Error::Parse(self.with_source(err)))?;
+                let session = self.sources[name].clone();
+                self.define(function_name, body, start, replace, &session)?;
+                self.compile_function(function_name, &ast);
+                Ok(())
+            }
+            Line::Code(code) => {
+                //modules added since the last line still need checking
+                self.build_functions()
+                    .map_err(|err| Error::Parse(self.with_source(err)))?;
+                let start = ast::Location::new(file, line_idx + 1, 1);
+                let ast = ast::AST::new_at(code, self.function_names(), start)
+                    .map_err(|err| Error::Parse(self.with_source(err)))?;
+                self.eval(ast)
+            }
+        }
+    }
+    //the stack from bottom to top
+    pub fn stack(&self) -> &[item::Item] {
+        self.data_stack.items()
+    }
+    //the stack the way `?` prints it
+    pub fn stack_string(&self) -> String {
+        self.data_stack.to_string()
+    }
+    pub fn turtle(&self) -> &turtle::Turtle {
+        &self.drawing_turtle
+    }
+    //everything the turtle has done, in order
+    pub fn segments(&self) -> &[turtle::TurtleHistoryFrame] {
+        self.drawing_turtle.history()
+    }
+    pub fn into_turtle(self) -> turtle::Turtle {
+        self.drawing_turtle
+    }
+    fn function_names(&self) -> Vec<char> {
+        self.functions.keys().copied().collect()
+    }
+    fn build_functions(&mut self) -> Result<(), ast::ParseError> {
+        let function_names = self.function_names();
+        let mut asts = Vec::new();
+        for (name, function) in &self.functions {
+            if self.compiled.contains(name) {
+                continue;
+            }
+            let ast = ast::AST::new_at(
+                function.body.to_string(),
+                function_names.clone(),
+                function.start.clone(),
+            )?;
+            asts.push((*name, ast));
+        }
+        for (name, ast) in asts {
+            self.compile_function(name, &ast);
+        }
+        Ok(())
+    }
+    //a function redefined while its old code is the last thing compiled, as happens in the repl, is written over
+    fn compile_function(&mut self, name: char, ast: &ast::AST) {
+        if let Some(start) = self.program.last_function(name) {
+            self.reclaim(start);
+        }
+        self.program.define(name, ast);
+        self.compiled.insert(name);
+    }
+    //drops the code compiled from start on, unless a quotation from it is still on a stack and could be run later
+    fn reclaim(&mut self, start: usize) {
+        let stacks = self.data_stack.items().iter().chain(self.dip_stack.items());
+        if !stacks.clone().any(|item| item.quotes_code_from(start)) {
+            self.program.truncate(start);
+        }
+    }
+    //defines a function, unless a different one by that name came from another file. `__` replaces it anyway
+    fn define(
+        &mut self,
+        name: char,
+        body: String,
+        start: ast::Location,
+        replace: bool,
+        source: &str,
+    ) -> Result<(), Error> {
+        if is_builtin(name) {
+            return Err(Error::Parse(
+                ast::ParseError::new(
+                    format!("`{name}` is a builtin, so a function can't be named `{name}`"),
+                    ast::Location::new(start.file, start.line, 1),
+                    None,
+                )
+                .with_source(source),
+            ));
+        }
+        if let Some(existing) = self.functions.get(&name)
+            && existing.body == body
+            && existing.start == start
+        {
+            return Ok(()); //the same definition, like when a file is run again, so it doesn't need compiling again
+        }
+        if let Some(existing) = self.functions.get(&name)
+            && !replace
+            && existing.start.file != start.file
+        {
+            if existing.body == body {
+                return Ok(()); //the same module loaded from two places
+            }
+            let defined_at = ast::Location::new(existing.start.file.clone(), existing.start.line, 1);
+            return Err(Error::Parse(
+                ast::ParseError::new(
+                    format!(
+                        "`{name}` is already defined at {defined_at}. Use `{name}__` to replace it on purpose, or import one of them under another name like `#import <module> {name}→<new name>`"
+                    ),
+                    ast::Location::new(start.file, start.line, 1),
+                    None,
+                )
+                .with_source(source),
+            ));
+        }
+        self.functions.insert(name, FunctionSource { body, start });
+        self.compiled.remove(&name); //compiled again before the next run
+        Ok(())
+    }
+    //loads everything a file's `#import` lines name, looking next to the file first
+    fn imports(&mut self, name: &str, source: &str) -> Result<(), Error> {
+        let dir = match self.folders.get(name) {
+            Some(dir) => dir.clone(),
+            None => Path::new(name).parent().unwrap_or(Path::new("")).to_path_buf(),
+        };
+        for (line_idx, line) in source.lines().enumerate() {
+            if let Some(import) = modules::parse_import(line) {
+                let at = ast::Location::new(Rc::from(name), line_idx + 1, 1);
+                self.import_from(import.name, &dir, &import.renames)
+                    .map_err(|err| import_error(err, at, source))?;
+            }
+        }
+        Ok(())
+    }
+    fn import_from(&mut self, name: &str, dir: &Path, renames: &[(char, char)]) -> Result<(), Error> {
+        let mut found = modules::find(name, dir, &self.search_path).map_err(Error::Module)?;
+        //under other names it is a different set of functions, so it can be loaded again
+        for (old, new) in renames {
+            found.key += &format!(" {old}→{new}");
+        }
+        if let Some(idx) = self.importing.iter().position(|(key, _)| *key == found.key) {
+            let mut circle: Vec<&str> = self.importing[idx..].iter().map(|(_, name)| name.as_str()).collect();
+            circle.push(&found.name);
+            return Err(Error::Module(format!(
+                "Modules can't import each other in a circle: {}",
+                circle.join(" -> ")
+            )));
+        }
+        if self.imported.contains(&found.key) {
+            return Ok(());
+        }
+        self.importing.push((found.key.clone(), found.name.clone()));
+        let result = self.add_renamed_module(&found.name, &found.source, renames);
+        self.importing.pop();
+        result?;
+        self.imported.insert(found.key);
+        Ok(())
+    }
+    //quotes the offending line from whichever file the error is in
+    fn with_source(&self, err: ast::ParseError) -> ast::ParseError {
+        match self.sources.get(&*err.at.file) {
+            Some(text) => err.with_source(text),
+            None => err,
+        }
+    }
+    fn eval(&mut self, syntax_tree: ast::AST) -> Result<(), Error> {
+        let mut run = Run {
+            trace: self.trace,
+            steps: 0,
+            max_steps: self.max_steps,
+            //a timeout too long to count to is the same as none
+            deadline: self
+                .timeout
+                .and_then(|timeout| Some((Instant::now().checked_add(timeout)?, timeout))),
+            printed: self.printed.take(),
+            strict: self.strict,
+        };
+        //the main code is only needed while it runs, so the program doesn't grow with every line of a session
+        let start = self.program.add_main(&syntax_tree);
+        let result = execute(
+            &self.program,
+            start,
+            &mut self.data_stack,
+            &mut self.dip_stack,
+            &mut self.drawing_turtle,
+            &mut run,
+        );
+        self.reclaim(start);
+        self.printed = run.printed;
+        result.map_err(|err| Error::Runtime(err.with_source(&self.sources)))
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//whether a line defines functions, or imports them, instead of running code
+pub fn is_definition(line: &str) -> bool {
+    modules::parse_import(line).is_some() || matches!(classify_line(line), Line::Function(..))
+}
+
+//like forward, absolute moves do nothing when given nil (or a quotation or an empty stack)
+fn pop_coordinate(data_stack: &mut stack::Stack<item::Item>) -> Option<f32> {
+    data_stack.pop()?.as_number().map(|coordinate| coordinate as f32)
+}
+fn dyadic_op(f: &dyn Fn(f64, f64) -> f64, default: f64, data_stack: &mut stack::Stack<item::Item>) {
+    let a = data_stack.pop().unwrap_or(item::Item::from_num(default));
+    let b = data_stack.pop().unwrap_or(item::Item::from_num(default));
+    data_stack.push(_apply_dyadic_op(f, a, b));
+}
+//there is probably some optimization that could be done here because you can quicken somthing once you know one arg is numeric
+fn _apply_dyadic_op(f: &dyn Fn(f64, f64) -> f64, a: item::Item, b: item::Item) -> item::Item {
+    zip_boxes(&a, &b, &|a, b| {
+        if a.itemtype == item::ItemType::Nil || b.itemtype == item::ItemType::Nil {
+            Some(item::Item::nil())
+        } else if a.itemtype == item::ItemType::Quotation || b.itemtype == item::ItemType::Quotation {
+            //there is no sensible way to do math on code
+            Some(item::Item::nil())
+        } else if a.itemtype == item::ItemType::Number && b.itemtype == item::ItemType::Number {
+            //a and b are both numbers, so just normally apply
+            let result = f(a.number.unwrap(), b.number.unwrap());
+            //protect the user from NaNs and the like
+            Some(if result.is_finite() { item::Item::from_num(result) } else { item::Item::nil() })
+        } else {
+            //one is a box, so apply to each of its elements, pairing them up with the other's if it is a box too
+            None
+        }
+    })
+}
+//builds the box of leaf applied to a and b's elements, going into boxes wherever leaf returns None.
+//a number paired with a box is paired with each of its elements. this is a loop instead of recursion so
+//long lists can't overflow the stack
+fn zip_boxes(
+    a: &item::Item,
+    b: &item::Item,
+    leaf: &dyn Fn(&item::Item, &item::Item) -> Option<item::Item>,
+) -> item::Item {
+    //pending is what is left to do, last first: Some for pairs to apply leaf to and None to box up the last three results
+    let mut pending = vec![Some((a, b))];
+    let mut results: Vec<item::Item> = vec![];
+    while let Some(next) = pending.pop() {
+        let Some((a, b)) = next else {
+            let b = results.pop().unwrap();
+            let g = results.pop().unwrap();
+            let r = results.pop().unwrap();
+            results.push(item::Item::from_box(item::DrwBox::new(r, g, b)));
+            continue;
+        };
+        if let Some(result) = leaf(a, b) {
+            results.push(result);
+            continue;
+        }
+        pending.push(None);
+        let [ar, ag, ab] = channels(a);
+        let [br, bg, bb] = channels(b);
+        pending.extend([Some((ab, bb)), Some((ag, bg)), Some((ar, br))]);
+    }
+    results.pop().unwrap()
+}
+//a box's elements, or a number three times so it pairs up with each of another box's elements
+fn channels(item: &item::Item) -> [&item::Item; 3] {
+    match &item.boxed {
+        Some(boxed) => [&boxed.r, &boxed.g, &boxed.b],
+        None => [item; 3],
+    }
+}
+fn monadic_op(f: &dyn Fn(f64) -> f64, default: f64, data_stack: &mut stack::Stack<item::Item>) {
+    let a = data_stack.pop().unwrap_or(item::Item::from_num(default));
+    data_stack.push(_apply_monadic_op(f, a));
+}
+fn _apply_monadic_op(f: &dyn Fn(f64) -> f64, a: item::Item) -> item::Item {
+    zip_boxes(&a, &a, &|a, _| match a.itemtype {
+        item::ItemType::Number => {
+            let result = f(a.number.unwrap());
+            Some(if result.is_finite() { item::Item::from_num(result) } else { item::Item::nil() })
+        }
+        item::ItemType::Nil | item::ItemType::Quotation => Some(item::Item::nil()),
+        item::ItemType::Box => None,
+    })
+}
+fn comp_op(f: &dyn Fn(f64, f64) -> bool, data_stack: &mut stack::Stack<item::Item>) {
+    let a = data_stack.pop();
+    let b = data_stack.pop();
+    //comparison operators return false by default, which is why they are special cased
+    data_stack.push(match a {
+        Some(a_item) => match b {
+            Some(b_item) => {
+                //turn f into a regular function so it can be passed to _apply_dyadic_op
+                let f: &dyn Fn(f64, f64) -> f64 = &|a, b| if f(a, b) { 1.0 } else { 0.0 };
+                _apply_dyadic_op(f, a_item, b_item)
+            }
+            None => item::Item::from_num(0.0),
+        },
+        None => item::Item::from_num(0.0),
+    });
+}
+
+//like _apply_monadic_op, but only is nil and has no nil guards
+
+fn apply_is_nil(a: item::Item) -> item::Item {
+    zip_boxes(&a, &a, &|a, _| match a.itemtype {
+        item::ItemType::Nil => Some(item::Item::from_num(1.0)),
+        item::ItemType::Number | item::ItemType::Quotation => Some(item::Item::from_num(0.0)),
+        item::ItemType::Box => None,
+    })
+}
+
+#[test]
+fn panics_test() {
+    let file = std::fs::read_to_string("tests/panics.drw").unwrap();
+    let mut interpreter = Interpreter::new();
+    for (line_idx, line) in file.lines().enumerate() {
+        let result = interpreter.run_line("tests/panics.drw", line);
+        let Line::Code(code) = classify_line(line) else {
+            continue;
+        };
+        if code.trim().is_empty() {
+            continue;
+        }
+        let loop_col = code.chars().position(|c| c == '[' || c == '{').unwrap() + 1;
+        let Err(Error::Runtime(err)) = result else {
+            panic!("line {} should have stopped with an error", line_idx + 1);
+        };
+        assert_eq!(
+            err.at,
+            ast::Location::new(Rc::from("tests/panics.drw"), line_idx + 1, loop_col)
+        );
+    }
+}
+
+#[test]
+fn nil_in_box_test() {
+    //nil (or a quotation) inside a box is ignored by drawing commands just like nil on its own, instead of panicking
+    for code in [
+        "0 , 255c",
+        ", 0 0B c",
+        "0 0 ,B s",
+        "0 0 ,B ^",
+        "0 0 ,B X",
+        "0 0 \"1'B H",
+        "0 0 0 0 ,B B ^",
+    ] {
+        let mut interpreter = Interpreter::new();
+        interpreter.run("nil_in_box.drw", code).unwrap();
+        assert_eq!(interpreter.stack(), &[], "{code}");
+        assert_eq!(interpreter.turtle().summary(), Interpreter::new().turtle().summary(), "{code}");
+    }
+}
+
+#[test]
+fn interpreter_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.add_module("double.drwm", "D_2*\n").unwrap();
+    interpreter.run("main.drw", "#comment\nQ_.*\n3QD 100^\n").unwrap();
+    assert_eq!(interpreter.stack(), &[item::Item::from_num(18.0)]);
+    assert_eq!(interpreter.segments().len(), 1);
+    let Err(Error::Runtime(err)) = interpreter.run("main.drw", "Q\n,[]1 2B[]") else {
+        panic!("iterating over a box should be an error");
+    };
+    assert_eq!(err.at, ast::Location::new(Rc::from("main.drw"), 2, 8));
+    assert!(Interpreter::new().add_module("bad.drwm", "D_2*\n+\n").is_err());
+    //the repl doesn't print the stack after these
+    assert!(is_definition("D_2*") && is_definition("#import list") && !is_definition("2D #import"));
+}
+
+#[test]
+fn strict_test() {
+    let strict = |code: &str| -> Option<String> {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_strict(true);
+        let err = interpreter.run("strict.drw", code).err()?;
+        Some(err.to_string().lines().take(2).collect::<Vec<&str>>().join("\n"))
+    };
+    assert_eq!(strict("1 2+ 3 4 5B U 0 0 255c 4 5 6B c 10^ 1(2)"), None);
+    assert_eq!(
+        strict("1 +").unwrap(),
+        "error: `+` takes 2 values but only 1 is on the stack here\n --> strict.drw:1:3"
+    );
+    assert!(strict("1)").unwrap().starts_with("error: `)` takes 1 value from the dip stack"));
+    assert!(strict("[]").unwrap().starts_with("error: `[` takes 1 value but the stack is empty"));
+    assert!(strict("1 2c").unwrap().starts_with("error: `c` takes 3 values but only 2"));
+    //nil reaching a drawing command
+    assert!(strict(",^").unwrap().starts_with("error: `^` was given `,`, which it would ignore"));
+    assert!(strict("0 , 255c").unwrap().starts_with("error: `c` was given `,`"));
+    assert!(strict(", 0 0B c").unwrap().starts_with("error: `c` was given `〚0 0 ,〛`"));
+    assert!(strict("\"1's").unwrap().starts_with("error: `s` was given `\"1'`"));
+    //without it, all of these carry on
+    for code in ["1 +", "1)", "[]", ",^", ", 0 0B c", "0 0 ,B s"] {
+        assert!(Interpreter::new().run("lenient.drw", code).is_ok(), "{code}");
+    }
+}
+
+#[test]
+fn limits_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_steps(Some(100));
+    //the repeat loop counts too, so an empty one still stops
+    let Err(Error::Runtime(err)) = interpreter.run("loop.drw", "10^1000000000[]") else {
+        panic!("should have run out of steps");
+    };
+    assert_eq!(err.at.col, 14);
+    assert_eq!(interpreter.segments().len(), 1); //the drawing is kept
+    interpreter.run("short.drw", "1{1}").unwrap_err();
+    interpreter.set_max_steps(None);
+    interpreter.set_timeout(Some(Duration::from_millis(50)));
+    let Err(Error::Runtime(err)) = interpreter.run("forever.drw", "1{1}") else {
+        panic!("should have run out of time");
+    };
+    assert_eq!(err.message, "Stopped after running for 0.05 seconds, the most allowed");
+    //one too long for the clock to reach just never runs out
+    interpreter.set_timeout(Some(Duration::MAX));
+    interpreter.run("long.drw", "1 2+").unwrap();
+}
+
+#[test]
+fn recursion_test() {
+    let mut interpreter = Interpreter::new();
+    //counting down is a tail call, so it can go on past the limit
+    interpreter.run("down.drw", "T_.0>[1:-T]\n1500000T").unwrap();
+    assert_eq!(interpreter.stack(), &[item::Item::zero()]);
+    //adding up after each call isn't, but it is still only limited by memory, up to a point
+    interpreter.run("sum.drw", "!\nΣ_.0>[.1:-Σ+]\n100000Σ").unwrap();
+    assert_eq!(interpreter.stack(), &[item::Item::from_num(5000050000.0)]);
+    let Err(Error::Runtime(err)) = interpreter.run("deep.drw", "!\n1500000Σ") else {
+        panic!("should have recursed too deep");
+    };
+    assert_eq!(err.message, format!("Recursed more than {MAX_CALL_DEPTH} calls deep, the most allowed"));
+    assert_eq!(err.at, ast::Location::new(Rc::from("sum.drw"), 2, 11)); //the call inside Σ
+    //a long list is boxes nested as deep as it is long. copying, printing and dropping one doesn't recurse either
+    let mut interpreter = Interpreter::new();
+    interpreter.run("list.drw", ",200000[0 0B].").unwrap();
+    assert_eq!(interpreter.stack_string().matches("〚0 0 ").count(), 400000);
+    //and neither does comparing one, doing math on one or checking if one is true
+    let deep = |element: f64, last| {
+        (0..200000).fold(item::Item::from_num(last), |list, _| {
+            item::Item::from_box(item::DrwBox::new(item::Item::from_num(element), item::Item::from_num(element), list))
+        })
+    };
+    for (code, expected) in [
+        (",200000[0 0B],200000[0 0B]|", vec![item::Item::from_num(1.0)]),
+        (",200000[0 0B];", vec![deep(0.0, 1.0)]),
+        ("1 200000[0 0B]1+", vec![deep(1.0, 2.0)]),
+        (",200000[0 0B]{2 0}", vec![]),
+        ("1 200000[0 0B]{2 0}", vec![item::Item::from_num(2.0)]),
+    ] {
+        let mut interpreter = Interpreter::new();
+        interpreter.run("deep.drw", code).unwrap();
+        assert!(interpreter.stack() == expected, "{code}");
+    }
+}
+
+#[test]
+fn program_growth_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.run_line("<repl>", "F_2*").unwrap();
+    interpreter.run_line("<repl>", "1F").unwrap();
+    let len = interpreter.program.code.len();
+    //running lines and redefining functions over and over takes no more room
+    for _ in 0..100 {
+        interpreter.run_line("<repl>", "F__3*").unwrap();
+        interpreter.run_line("<repl>", "F0* 2[1+] \"1-'`").unwrap();
+    }
+    assert_eq!(interpreter.program.code.len(), len);
+    assert_eq!(interpreter.stack(), &[item::Item::from_num(-1.0)]);
+    //neither does running the same file again
+    for _ in 0..100 {
+        interpreter.run("again.drw", "G_1+\nK_G G\n2K").unwrap();
+    }
+    let len = interpreter.program.code.len();
+    interpreter.run("again.drw", "G_1+\nK_G G\n2K").unwrap();
+    assert_eq!(interpreter.program.code.len(), len);
+    //but a quotation left on the stack keeps its code around until it is run
+    interpreter.run_line("<repl>", "\"5 6+'").unwrap();
+    interpreter.run_line("<repl>", "1 2+").unwrap();
+    interpreter.run_line("<repl>", ":`").unwrap();
+    assert_eq!(interpreter.stack().last(), Some(&item::Item::from_num(11.0)));
+}
+
+#[test]
+fn import_test() {
+    let dir = std::env::temp_dir().join(format!("drw_import_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let module = |name: &str, source: &str| std::fs::write(dir.join(name), source).unwrap();
+    module("shapes.drwm", "#import list\n□_4[.^90~]!");
+    module("more.drwm", "#import shapes\n#import list\n△_3[.^120~]!");
+    module("range.drwm", "⇡_3*\n⇈_⇡⇡");
+    module("a.drwm", "#import b\nQ_1");
+    module("b.drwm", "#import a\nD_2");
+    let main = dir.join("main.drw");
+    let mut interpreter = Interpreter::new();
+    //list comes from the built in library, and is only loaded once even though two modules import it
+    interpreter
+        .run(&main.to_string_lossy(), "#import more\n50□ 3⇡Σ")
+        .unwrap();
+    assert_eq!(interpreter.segments().len(), 4);
+    assert_eq!(interpreter.stack(), &[item::Item::from_num(6.0)]);
+    assert_eq!(interpreter.imported.len(), 3);
+    //range.drwm's ⇡ would clash with list's, so it is loaded as ↑, along with the function that calls it
+    interpreter
+        .run(&main.to_string_lossy(), "#import range ⇡→↑ ⇈->↟\n!2↑ 2↟ 3⇡Σ")
+        .unwrap();
+    assert_eq!(
+        interpreter.stack(),
+        &[item::Item::from_num(6.0), item::Item::from_num(18.0), item::Item::from_num(6.0)]
+    );
+    let Err(Error::Parse(err)) = interpreter.run(&main.to_string_lossy(), "#import range ⇡→↑ Q→D") else {
+        panic!("range doesn't define Q");
+    };
+    assert!(err.message.contains("doesn't define `Q`"), "{}", err.message);
+    let Err(Error::Parse(err)) = interpreter.run(&main.to_string_lossy(), "\n#import range ⇡→+") else {
+        panic!("+ is a builtin");
+    };
+    assert_eq!(err.message, "`⇡` can't be renamed to `+`, which is a builtin");
+    assert_eq!(err.at, ast::Location::new(Rc::from(main.to_string_lossy()), 2, 1));
+    let Err(Error::Parse(err)) = interpreter.run(&main.to_string_lossy(), "\n#import a") else {
+        panic!("a and b import each other");
+    };
+    assert!(err.message.starts_with("Modules can't import each other in a circle"), "{}", err.message);
+    assert_eq!(err.at.file.as_ref(), dir.join("b.drwm").to_string_lossy());
+    let Err(Error::Parse(err)) = interpreter.run(&main.to_string_lossy(), "\n#import nope") else {
+        panic!("there is no nope module");
+    };
+    assert_eq!(err.at.line, 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn conflict_test() {
+    let mut interpreter = Interpreter::new();
+    interpreter.add_module("a.drwm", "#one\nQ_1").unwrap();
+    let Err(Error::Parse(err)) = interpreter.add_module("b.drwm", "Q_2") else {
+        panic!("Q is defined in both a and b");
+    };
+    assert!(err.message.starts_with("`Q` is already defined at a.drwm:2:1."), "{}", err.message);
+    assert_eq!(err.at, ast::Location::new(Rc::from("b.drwm"), 1, 1));
+    //loading the same definition twice is fine, and so is redefining in the same file
+    interpreter.add_module("a copy.drwm", "Q_1").unwrap();
+    interpreter.run("main.drw", "D_2\nD_3\nQ D").unwrap();
+    assert_eq!(interpreter.stack(), &[item::Item::from_num(1.0), item::Item::from_num(3.0)]);
+    interpreter.run("main.drw", "Q__4\n!!Q").unwrap();
+    assert_eq!(interpreter.stack(), &[item::Item::from_num(4.0)]);
+    assert!(matches!(interpreter.run_line("<repl>", "D_5"), Err(Error::Parse(_))));
+    interpreter.run_line("<repl>", "D__5").unwrap();
+    interpreter.run_line("<repl>", "!D").unwrap();
+    assert_eq!(interpreter.stack(), &[item::Item::from_num(5.0)]);
+}
+
+#[test]
+fn builtin_name_test() {
+    let mut interpreter = Interpreter::new();
+    let Err(Error::Parse(err)) = interpreter.run("main.drw", "1\nx_2") else {
+        panic!("x is a builtin");
+    };
+    assert_eq!(err.message, "`x` is a builtin, so a function can't be named `x`");
+    assert_eq!(err.at, ast::Location::new(Rc::from("main.drw"), 2, 1));
+    assert!(matches!(interpreter.add_module("a.drwm", "[_1"), Err(Error::Parse(_))));
+    assert!(matches!(interpreter.run_line("<repl>", "h__2"), Err(Error::Parse(_))));
+    //replacing a function that isn't a builtin still works
+    interpreter.run_line("<repl>", "Q__2").unwrap();
+    interpreter.run_line("<repl>", "Q").unwrap();
+    assert_eq!(interpreter.stack(), &[item::Item::from_num(2.0)]);
+}
@@ -1,25 +1,43 @@
 //TODO use an enum instead of a tagged union thingy
 use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum ItemType {
     Number,
     Box,
     Nil,
+    Quotation,
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct DrwBox {
     pub r: Item,
     pub g: Item,
     pub b: Item,
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Item {
     pub itemtype: ItemType,
     pub number: Option<f64>,
-    pub boxed: Option<Box<DrwBox>>,
+    pub boxed: Option<Rc<DrwBox>>, //shared too, since lists are boxes nested as deep as they are long
+    pub quoted: Option<Rc<Quotation>>, //shared so copying a quotation around the stack is cheap
+}
+
+//a quotation's code, and where its compiled instructions start
+#[derive(PartialEq, Clone, Debug)]
+pub struct Quotation {
+    pub code: Rc<str>, //the code it is in, shared with everything else compiled from it
+    pub span: Range<usize>, //the bytes of code between its brackets
+    pub start: usize,
+}
+
+impl Quotation {
+    pub fn text(&self) -> &str {
+        &self.code[self.span.clone()]
+    }
 }
 
 impl DrwBox {
@@ -41,13 +59,7 @@ impl DrwBox {
 
 impl fmt::Display for DrwBox {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "〚{} {} {}〛",
-            self.clone().r.to_string(),
-            self.clone().g.to_string(),
-            self.clone().b.to_string(),
-        )
+        write!(f, "〚{} {} {}〛", self.r, self.g, self.b)
     }
 }
 
@@ -57,13 +69,23 @@ impl Item {
             itemtype: ItemType::Number,
             number: Some(item),
             boxed: None,
+            quoted: None,
         }
     }
     pub fn from_box(item: DrwBox) -> Item {
         Item {
             itemtype: ItemType::Box,
             number: None,
-            boxed: Some(Box::new(item)),
+            boxed: Some(Rc::new(item)),
+            quoted: None,
+        }
+    }
+    pub fn from_quotation(quotation: Rc<Quotation>) -> Item {
+        Item {
+            itemtype: ItemType::Quotation,
+            number: None,
+            boxed: None,
+            quoted: Some(quotation),
         }
     }
     pub fn nil() -> Item {
@@ -71,6 +93,7 @@ impl Item {
             itemtype: ItemType::Nil,
             number: None,
             boxed: None,
+            quoted: None,
         }
     }
     pub fn zero() -> Item {
@@ -78,55 +101,143 @@ impl Item {
             itemtype: ItemType::Number,
             number: Some(0.0),
             boxed: None,
+            quoted: None,
         }
     }
     pub fn get_number(self) -> f64 {
         match self.itemtype {
             ItemType::Number => self.number.unwrap(),
-            ItemType::Box => self.boxed.unwrap().r.get_number(),
-            ItemType::Nil => {
-                panic!("Tried to call get_number on a nil value. This is a bug in the interpreter")
+            ItemType::Box => self.red_channel().clone().get_number(),
+            ItemType::Nil | ItemType::Quotation => {
+                panic!("Tried to call get_number on a nil value or a quotation. This is a bug in the interpreter")
             }
         }
     }
     pub fn get_number_or(self, or: f64) -> f64 {
-        match self.itemtype {
-            ItemType::Number => self.number.unwrap(),
-            ItemType::Box => self.boxed.unwrap().r.get_number(),
-            ItemType::Nil => or,
+        self.as_number().unwrap_or(or)
+    }
+    //the number a command that takes one would use, None for nil and quotations, even down a box's red channels
+    pub fn as_number(&self) -> Option<f64> {
+        self.red_channel().number
+    }
+    //the innermost item down the red channels of boxes in boxes
+    fn red_channel(&self) -> &Item {
+        let mut item = self;
+        while let Some(boxed) = &item.boxed {
+            item = &boxed.r;
         }
+        item
     }
-    pub fn get_box(self) -> DrwBox {
+    pub fn get_box(mut self) -> DrwBox {
         match self.itemtype {
-            ItemType::Box => *(self.boxed.unwrap()),
+            ItemType::Box => Rc::unwrap_or_clone(self.boxed.take().unwrap()),
             ItemType::Number => DrwBox::from_nums(self.number.unwrap(), 0.0, 0.0),
+            ItemType::Quotation => DrwBox::new(self, Item::zero(), Item::zero()),
             ItemType::Nil => {
                 panic!("Tried to call get_box on a nil value. This is a bug in the interpreter")
             }
         }
     }
-    pub fn is_truthy(self) -> bool {
-        match self.itemtype {
-            ItemType::Number => self.number.unwrap() != 0.0,
-            ItemType::Nil => false,
-            ItemType::Box => {
-                let item = *(self.boxed.unwrap());
-                if item != DrwBox::from_nums(0.0, 0.0, 0.0) {
-                    item.r.is_truthy() || item.g.is_truthy() || item.b.is_truthy()
-                } else {
+    //a box is truthy if anything in it is, which is looked for with a loop so deep boxes can't overflow the stack
+    pub fn is_truthy(&self) -> bool {
+        let mut pending = vec![self];
+        while let Some(item) = pending.pop() {
+            let truthy = match item.itemtype {
+                ItemType::Number => item.number.unwrap() != 0.0,
+                ItemType::Nil => false,
+                ItemType::Quotation => true,
+                ItemType::Box => {
+                    let boxed = item.boxed.as_ref().unwrap();
+                    pending.extend([&boxed.b, &boxed.g, &boxed.r]);
                     false
                 }
+            };
+            if truthy {
+                return true;
             }
         }
+        false
+    }
+    //whether this is a quotation compiled at or after start, or a box with one in it
+    pub fn quotes_code_from(&self, start: usize) -> bool {
+        let mut pending = vec![self];
+        while let Some(item) = pending.pop() {
+            if item.quoted.as_ref().is_some_and(|quotation| quotation.start >= start) {
+                return true;
+            }
+            if let Some(boxed) = &item.boxed {
+                pending.extend([&boxed.r, &boxed.g, &boxed.b]);
+            }
+        }
+        false
+    }
+}
+
+impl PartialEq for DrwBox {
+    fn eq(&self, other: &DrwBox) -> bool {
+        self.r == other.r && self.g == other.g && self.b == other.b
+    }
+}
+
+impl PartialEq for Item {
+    //compared with a loop rather than derived, for the same reason as Display and Drop below
+    fn eq(&self, other: &Item) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some((a, b)) = pending.pop() {
+            if a.itemtype != b.itemtype || a.number != b.number || a.quoted != b.quoted {
+                return false;
+            }
+            match (&a.boxed, &b.boxed) {
+                (Some(a), Some(b)) if !Rc::ptr_eq(a, b) => {
+                    pending.extend([(&a.r, &b.r), (&a.g, &b.g), (&a.b, &b.b)]);
+                }
+                (Some(_), Some(_)) | (None, None) => {}
+                _ => return false,
+            }
+        }
+        true
     }
 }
 
 impl fmt::Display for Item {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.itemtype {
-            ItemType::Number => write!(f, "{}", self.clone().get_number().to_string()),
-            ItemType::Nil => write!(f, ","),
-            ItemType::Box => write!(f, "{}", self.clone().get_box().to_string()),
+        //boxes in boxes are written with a loop instead of recursively, so long lists can't overflow the stack.
+        //pending is what is left to write, last first: Ok for items and Err for the text between them
+        let mut pending: Vec<Result<&Item, &str>> = vec![Ok(self)];
+        while let Some(next) = pending.pop() {
+            let item = match next {
+                Ok(item) => item,
+                Err(text) => {
+                    write!(f, "{text}")?;
+                    continue;
+                }
+            };
+            match item.itemtype {
+                ItemType::Number => write!(f, "{}", item.number.unwrap())?,
+                ItemType::Nil => write!(f, ",")?,
+                ItemType::Box => {
+                    let boxed = item.boxed.as_ref().unwrap();
+                    write!(f, "〚")?;
+                    pending.extend([Err("〛"), Ok(&boxed.b), Err(" "), Ok(&boxed.g), Err(" "), Ok(&boxed.r)]);
+                }
+                ItemType::Quotation => write!(f, "\"{}'", item.quoted.as_ref().unwrap().text())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Item {
+    //dropping a box drops the boxes in it, so those are taken apart with a loop too
+    fn drop(&mut self) {
+        let mut pending: Vec<Rc<DrwBox>> = self.boxed.take().into_iter().collect();
+        while let Some(boxed) = pending.pop() {
+            //a box still used somewhere else stays whole
+            if let Ok(mut boxed) = Rc::try_unwrap(boxed) {
+                for item in [&mut boxed.r, &mut boxed.g, &mut boxed.b] {
+                    pending.extend(item.boxed.take());
+                }
+            }
         }
     }
 }
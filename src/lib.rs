@@ -0,0 +1,19 @@
+//drw as a library, so drawings can be made from rust code without going through the command line
+//the drw binary in main.rs is built on top of this
+
+pub mod ast;
+pub mod builtins;
+pub mod bytecode;
+pub mod check;
+pub mod gif;
+pub mod interpreter;
+pub mod item;
+pub mod modules;
+pub mod png;
+pub mod raster;
+pub mod stack;
+pub mod svg;
+pub mod testing;
+pub mod turtle;
+
+pub use interpreter::{Error, Interpreter, RuntimeError};
@@ -1,496 +1,387 @@
-use clap::Parser;
-use std::collections::HashMap;
+use clap::builder::RangedU64ValueParser;
+use clap::{Parser, Subcommand};
+use drw::{Interpreter, builtins, gif, interpreter, png, raster, svg, testing, turtle};
 //use std::env;
 use std::fs;
-mod ast;
-mod item;
-mod stack;
-mod turtle;
-
-const THE_NUMBER_OF_RADIANS_IN_A_CIRCLE: f64 = 6.283185307179586;
-const EULERS_NUMBER: f64 = 2.7182818284590452;
-const THE_NUMBER_OF_DEGREES_IN_A_CIRCLE: f64 = 360.0;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
 
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, disable_help_subcommand = true)]
 struct Args {
-    /// File to run
-    file: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// File to run. Starts a REPL if left out
+    file: Option<String>,
+
+    /// Module to use, by path or by name from DRW_PATH or the built in library. Can be given more than once
+    #[arg(short, long)]
+    mods: Vec<String>,
+
+    /// Write the drawing to a file instead of opening a window (.svg, .png or .gif)
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Width in pixels of a rasterized output file
+    #[arg(long, default_value_t = 800, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    width: usize,
+
+    /// Height in pixels of a rasterized output file
+    #[arg(long, default_value_t = 450, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    height: usize,
+
+    /// Samples per pixel along each axis when rasterizing, for smoother edges
+    #[arg(long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    supersample: usize,
+
+    /// Segments added in each frame of an animated output file
+    #[arg(long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    frame_segments: usize,
+
+    /// Hundredths of a second each frame of an animated output file is shown for
+    #[arg(long, default_value_t = 4)]
+    frame_delay: u16,
+
+    /// Hundredths of a second the finished drawing is shown for before an animation loops
+    #[arg(long, default_value_t = 200)]
+    hold: u16,
+
+    /// Never open a window; print a summary of the drawing instead
+    #[arg(long)]
+    no_window: bool,
 
-    /// Modules to use
+    /// Draw the turtle's path one segment at a time in the window
     #[arg(short, long)]
-    mods: Option<String>,
+    animate: bool,
+
+    /// Print every step to stderr with where it is and both stacks after it
+    #[arg(long, global = true)]
+    trace: bool,
+
+    /// Stop with an error after running this many steps
+    #[arg(long, global = true)]
+    max_steps: Option<u64>,
+
+    /// Stop with an error after running for this many seconds
+    #[arg(long, global = true)]
+    #[arg(value_parser = parse_seconds)]
+    timeout: Option<Duration>,
+
+    /// Stop with an error where the stack runs out or a drawing command is given nil, instead of filling in a default
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Segments drawn per second when animating
+    #[arg(long, default_value_t = 60.0)]
+    speed: f32,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run every .drw file in a folder and compare what each leaves behind with its .expected file
+    Test {
+        /// Folder to look for tests in
+        #[arg(default_value = "tests")]
+        dir: String,
+
+        /// Write the .expected files from what the tests do now instead of checking them
+        #[arg(long)]
+        bless: bool,
+    },
+    /// Work out what each function takes and leaves on the stack, and warn where the stack would run out
+    Check {
+        /// File to check
+        file: String,
+    },
+    /// Print what a builtin command or bracket does
+    Help {
+        /// The command's glyph, like + or [
+        #[arg(required_unless_present = "all")]
+        glyph: Option<String>,
+
+        /// Print every builtin
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+//a number of seconds more than 0, for --timeout
+fn parse_seconds(text: &str) -> Result<Duration, String> {
+    let seconds: f64 = text.parse().map_err(|_| format!("{text} isn't a number"))?;
+    match Duration::try_from_secs_f64(seconds) {
+        Ok(duration) if !duration.is_zero() => Ok(duration),
+        _ => Err(format!("{text} isn't a number of seconds more than 0")),
+    }
 }
 
 fn readfile(name: String) -> String {
-    fs::read_to_string(&name).expect(&format!("Unable to find file: {name}"))
+    fs::read_to_string(&name).unwrap_or_else(|_| panic!("Unable to find file: {name}"))
 }
 
-fn read() -> Result<(ast::AST, HashMap<char, ast::AST>), String> {
-    let args = Args::parse();
-    let mut program = readfile(args.file);
-    let _ = program.pop();
-    let mut functions: HashMap<char, String> = HashMap::new();
-    let mut non_function: Vec<String> = Vec::new();
-    for line in program.lines() {
-        //TODO refactor this its bad
-        let line_chars: Vec<char> = line.chars().collect();
-        let mut line_chars_no_comments: Vec<char> = Vec::new();
-        for i in line_chars {
-            if i == '#' {
-                break; //comment
-            }
-            line_chars_no_comments.push(i)
+//passes on the flags that change how a run goes
+fn set_limits(args: &Args, interpreter: &mut Interpreter) {
+    interpreter.set_trace(args.trace);
+    interpreter.set_strict(args.strict);
+    interpreter.set_max_steps(args.max_steps);
+    interpreter.set_timeout(args.timeout);
+}
+
+//passes on the limits and loads the modules from the command line
+fn set_up(args: &Args, interpreter: &mut Interpreter) -> Result<(), drw::Error> {
+    set_limits(args, interpreter);
+    for module in &args.mods {
+        interpreter.import(module)?;
+    }
+    Ok(())
+}
+
+//makes an interpreter with the modules from the command line loaded
+fn load_modules(args: &Args) -> Result<Interpreter, drw::Error> {
+    let mut interpreter = Interpreter::new();
+    set_up(args, &mut interpreter)?;
+    Ok(interpreter)
+}
+
+//runs one line at a time against a stack and turtle that last for the whole session
+fn repl(args: &Args) -> ExitCode {
+    let mut interpreter = match load_modules(args) {
+        Ok(interpreter) => interpreter,
+        Err(err) => {
+            println!("{}", err);
+            return ExitCode::FAILURE;
         }
-        if line_chars_no_comments.len() == 1 || line_chars_no_comments.len() == 0 {
-            //is (short) line of code
-            non_function.push(line_chars_no_comments.into_iter().collect());
-        } else {
-            if line_chars_no_comments[1] == '_' {
-                //Is function
-                functions.insert(
-                    line_chars_no_comments[0],
-                    line_chars_no_comments[2..].to_vec().into_iter().collect(),
-                );
-            } else {
-                //is line of code
-                non_function.push(line_chars_no_comments.into_iter().collect());
-            }
+    };
+    //only prompt a person, so piped input gives clean output
+    let interactive = io::stdin().is_terminal();
+    let mut input = String::new();
+    loop {
+        if interactive {
+            print!("drw> ");
+            let _ = io::stdout().flush();
         }
-    }
-    let main_code = non_function.join(" ");
-
-    if let Some(modules) = args.mods {
-        for module in modules.split(' ') {
-            let module = readfile(module.to_string());
-            for line in module.lines() {
-                let no_comment: Vec<char>;
-                if let Some(pos) = line.find('#') {
-                    no_comment = line[..pos].chars().collect();
-                } else {
-                    no_comment = line.chars().collect();
-                }
-                match no_comment.len(){
-		    0=>continue,
-		    1=>return Err(format!("Modules must be all functions and all functions are delinated by a name and a binding (`_`). `{}` does not contain a binding",no_comment.into_iter().collect::<String>())),
-		    2.. =>{
-			if no_comment[1]=='_'{
-			    functions.insert(
-				no_comment[0],
-				no_comment[2..].to_vec().into_iter().collect(),
-			    );
-			}
-		    }
-		}
-            }
+        input.clear();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        let line = input.trim_end_matches(['\n', '\r']);
+        match interpreter.run_line("<repl>", line) {
+            Ok(()) if !interpreter::is_definition(line) => print!("{}", interpreter.stack_string()),
+            Ok(()) => {}
+            Err(err) => println!("{}", err),
         }
     }
-
-    let mut function_names: Vec<char> = Vec::new();
-    for i in functions.keys() {
-        function_names.push(*i);
-    }
-    let mut function_asts: HashMap<char, ast::AST> = HashMap::new();
-    let mut func_err: String = String::new();
-    let mut has_err = false;
-    for (name, body) in &functions {
-        let ast = ast::AST::new(body.to_string(), function_names.clone());
-        match ast {
-            Ok(goodast) => function_asts.insert(*name, goodast),
-            Err(err) => {
-                has_err = true;
-                func_err = err;
-                break;
-            }
-        };
-    }
-    if has_err {
-        return Err(func_err);
+    if interactive {
+        println!();
     }
-    let main_ast = ast::AST::new(main_code, function_names);
-    match main_ast {
-        Ok(goodmain_ast) => Ok((goodmain_ast, function_asts)),
-        Err(err) => Err(err),
+    match show(args, interpreter.into_turtle()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            println!("{}", err);
+            ExitCode::FAILURE
+        }
     }
 }
-fn eval(
-    syntax_tree: ast::AST,
-    functions: HashMap<char, ast::AST>,
-    data_stack: &mut stack::Stack<item::Item>,
-    dip_stack: &mut stack::Stack<item::Item>,
-    drawing_turtle: &mut turtle::Turtle,
-) -> String {
-    evallist(
-        syntax_tree.node.children.unwrap(),
-        functions,
-        data_stack,
-        dip_stack,
-        drawing_turtle,
-    );
-    return data_stack.to_string();
-}
 
-#[allow(unreachable_patterns)]
-fn evallist(
-    syntax_tree: Vec<ast::ASTnode>,
-    functions: HashMap<char, ast::AST>,
-    data_stack: &mut stack::Stack<item::Item>,
-    dip_stack: &mut stack::Stack<item::Item>,
-    drawing_turtle: &mut turtle::Turtle,
-) {
-    for node in syntax_tree {
-        match node.nodetype {
-            ast::ASTnodeType::Number => data_stack.push(item::Item::from_num(node.number.unwrap())),
-            ast::ASTnodeType::Command => match node.command.unwrap() {
-                ast::Commands::ForwardCommand => {
-                    if data_stack.peek().unwrap_or(&item::Item::nil()).itemtype
-                        == item::ItemType::Nil
-                    {
-                        let _throwaway = data_stack.pop();
-                    } else {
-                        drawing_turtle.forward(data_stack.pop().unwrap().get_number_or(0.0) as f32);
-                        drawing_turtle.push();
-                    }
-                }
-                ast::Commands::TurnCommand => drawing_turtle.turn(
-                    data_stack
-                        .pop()
-                        .unwrap_or(item::Item::zero())
-                        .get_number_or(0.0) as f32,
-                ),
-                ast::Commands::DuplicateCommand => data_stack.dup(item::Item::zero()),
-                ast::Commands::SwapCommand => data_stack.swap(item::Item::zero()),
-                ast::Commands::PopCommand => {
-                    let _throwaway = data_stack.pop();
-                }
-                ast::Commands::RotCommand => {
-                    //a b c -> c a b
-                    let a = data_stack.pop().unwrap_or(item::Item::zero());
-                    let b = data_stack.pop().unwrap_or(item::Item::zero());
-                    let c = data_stack.pop().unwrap_or(item::Item::zero());
-
-                    data_stack.push(b);
-                    data_stack.push(a);
-                    data_stack.push(c);
-                }
-                ast::Commands::UnrotCommand => {
-                    //a b c -> b c a
-                    let a = data_stack.pop().unwrap_or(item::Item::zero());
-                    let b = data_stack.pop().unwrap_or(item::Item::zero());
-                    let c = data_stack.pop().unwrap_or(item::Item::zero());
-
-                    data_stack.push(a);
-                    data_stack.push(c);
-                    data_stack.push(b);
-                }
-
-                ast::Commands::AddCommand => dyadic_op(&|a, b| a + b, 0.0, data_stack),
-                ast::Commands::SubtractCommand => dyadic_op(&|a, b| a - b, 0.0, data_stack),
-                ast::Commands::MultiplyCommand => dyadic_op(&|a, b| a * b, 1.0, data_stack),
-                ast::Commands::DivideCommand => dyadic_op(&|a, b| a / b, 1.0, data_stack),
-                ast::Commands::ModuloCommand => dyadic_op(&|a, b| a % b, 1.0, data_stack),
-                ast::Commands::DegreeCommand => {
-                    if drawing_turtle.using_degrees() {
-                        data_stack.push(item::Item::from_num(THE_NUMBER_OF_DEGREES_IN_A_CIRCLE));
-                    } else {
-                        drawing_turtle.set_turn_mode(turtle::TurnModes::DEGREE);
-                    }
-                }
-                ast::Commands::RadianCommand => {
-                    if drawing_turtle.using_radians() {
-                        data_stack.push(item::Item::from_num(THE_NUMBER_OF_RADIANS_IN_A_CIRCLE));
-                    } else {
-                        drawing_turtle.set_turn_mode(turtle::TurnModes::RADIAN);
-                    }
-                }
-                ast::Commands::ColorCommand => {
-                    if data_stack.peek().unwrap_or(&item::Item::zero()).itemtype
-                        == item::ItemType::Number
-                    {
-                        let r = data_stack
-                            .pop()
-                            .unwrap_or(item::Item::from_num(255.0))
-                            .get_number();
-                        let g = data_stack
-                            .pop()
-                            .unwrap_or(item::Item::from_num(255.0))
-                            .get_number();
-                        let b = data_stack
-                            .pop()
-                            .unwrap_or(item::Item::from_num(255.0))
-                            .get_number();
-                        drawing_turtle.set_color(r as u8, g as u8, b as u8);
-                    } else if data_stack.peek().unwrap().itemtype == item::ItemType::Nil {
-                        //unwrap_or is unneeded because empty stack was checked for in isnum check
-                        let _throwaway = data_stack.pop();
-                    } else {
-                        let the_box = data_stack
-                            .pop()
-                            .unwrap_or(item::Item::from_box(item::DrwBox::from_nums(
-                                255.0, 255.0, 255.0,
-                            )))
-                            .get_box();
-                        drawing_turtle.set_color(
-                            the_box.r.get_number() as u8,
-                            the_box.g.get_number() as u8,
-                            the_box.b.get_number() as u8,
-                        );
-                    }
-                }
-                ast::Commands::PenDownCommand => drawing_turtle.pen_down(),
-                ast::Commands::PenUpCommand => drawing_turtle.pen_up(),
-                ast::Commands::SizeCommand => {
-                    if data_stack.peek().unwrap_or(&item::Item::nil()).itemtype
-                        == item::ItemType::Nil
-                    {
-                        let _throwaway = data_stack.pop();
-                    } else {
-                        drawing_turtle.set_pen_size(data_stack.pop().unwrap().get_number() as f32);
-                    }
-                }
-                ast::Commands::DebugCommand => println!("{}", data_stack.to_string()),
-                ast::Commands::PowerCommand => dyadic_op(&|a, b| a.powf(b), 1.0, data_stack),
-                ast::Commands::LogCommand => dyadic_op(&|a, b| a.log(b), EULERS_NUMBER, data_stack),
-                ast::Commands::EulerNumCommand => {
-                    data_stack.push(item::Item::from_num(EULERS_NUMBER))
-                }
-                ast::Commands::SquareRootCommand => monadic_op(&|a| a.sqrt(), 1.0, data_stack),
-                ast::Commands::SineCommand => monadic_op(&|a| a.sin(), 0.0, data_stack),
-                ast::Commands::CeilingCommand => monadic_op(&|a| a.ceil(), 0.0, data_stack),
-                ast::Commands::FloorCommand => monadic_op(&|a| a.floor(), 0.0, data_stack),
-                ast::Commands::RoundCommand => monadic_op(&|a| a.round(), 1.0, data_stack),
-                ast::Commands::LessThanCommand => comp_op(&|a, b| a < b, data_stack),
-                ast::Commands::GreaterThanCommand => comp_op(&|a, b| a > b, data_stack),
-                ast::Commands::EqualCommand => comp_op(&|a, b| a == b, data_stack),
-                ast::Commands::DipCommand => data_stack.dip(dip_stack, item::Item::zero()),
-                ast::Commands::UndipCommand => dip_stack.dip(data_stack, item::Item::zero()),
-                ast::Commands::BoxCommand => {
-                    let r = data_stack.pop().unwrap_or(item::Item::zero());
-                    let g = data_stack.pop().unwrap_or(item::Item::zero());
-                    let b = data_stack.pop().unwrap_or(item::Item::zero());
-                    data_stack.push(item::Item::from_box(item::DrwBox::new(r, g, b)));
-                }
-                ast::Commands::UnboxCommand => {
-                    if data_stack.peek().unwrap_or(&item::Item::zero()).itemtype
-                        != item::ItemType::Nil
-                    {
-                        let the_box = data_stack
-                            .pop()
-                            .unwrap_or(item::Item::from_box(item::DrwBox::from_nums(0.0, 0.0, 0.0)))
-                            .get_box();
-                        data_stack.push(the_box.b);
-                        data_stack.push(the_box.g);
-                        data_stack.push(the_box.r);
-                    } else {
-                        //to help reduce nil checks in code
-                        data_stack.push(item::Item::nil());
-                        data_stack.push(item::Item::nil());
-                    }
-                }
-                ast::Commands::IsBoxCommand => {
-                    let item = data_stack.pop();
-                    data_stack.push(item::Item::from_num(match item {
-                        Some(i) => {
-                            if i.itemtype == item::ItemType::Box {
-                                1.0
-                            } else {
-                                0.0
-                            }
-                        }
-                        None => 0.0,
-                    }))
-                }
-                ast::Commands::NilCommand => data_stack.push(item::Item::nil()),
-                ast::Commands::IsNilCommand => {
-                    let a = data_stack.pop().unwrap_or(item::Item::zero());
-                    data_stack.push(apply_is_nil(a));
-                }
-                ast::Commands::MatchCommand => {
-                    if let Some(a) = data_stack.pop() {
-                        if let Some(b) = data_stack.pop() {
-                            data_stack.push(item::Item::from_num(if a == b { 1.0 } else { 0.0 }));
-                        }else{
-			    data_stack.push(item::Item::zero());
-			}
-                    } else {
-                        data_stack.push(item::Item::zero());
-                    }
-                }
-                _ => unreachable!(), //should never happen. make this "a bug was found in the interpreter" error
-            },
-            ast::ASTnodeType::ControlStructure => match node.structure.unwrap() {
-                ast::ControlStructures::RepeatLoop => {
-                    let n = data_stack.pop().unwrap_or(item::Item::zero());
-                    if n.itemtype == item::ItemType::Box {
-                        panic!("Cannot iterate over boxes!\n Got: {n}")
-                    }
-                    let num: f64 = n.get_number_or(0.0);
-                    if num != num.floor() {
-                        panic!("Cannot iterate a non-whole number of times!\n Got: {num}")
-                    }
-                    if num < 0.0 {
-                        panic!(
-                            "Cannot iterate a negative number of times! (drw isn't Uiua)\n Got: {num}"
-                        )
-                    }
-                    for _ in 0..num as u64 {
-                        evallist(
-                            node.children.clone().unwrap(),
-                            functions.clone(),
-                            data_stack,
-                            dip_stack,
-                            drawing_turtle,
-                        );
-                    }
-                }
-                ast::ControlStructures::WhileLoop => {
-                    while data_stack.pop().unwrap().is_truthy() {
-                        evallist(
-                            node.children.clone().unwrap(),
-                            functions.clone(),
-                            data_stack,
-                            dip_stack,
-                            drawing_turtle,
-                        );
-                    }
-                }
-            },
-            ast::ASTnodeType::Function => evallist(
-                functions
-                    .get(&node.function.unwrap())
-                    .unwrap()
-                    .node
-                    .children
-                    .clone()
-                    .unwrap(),
-                functions.clone(),
-                data_stack,
-                dip_stack,
+fn write_output(args: &Args, path: &str, drawing_turtle: &turtle::Turtle) -> Result<(), String> {
+    let contents = match path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()) {
+        Some(ext) if ext == "svg" => svg::from_turtle(drawing_turtle).into_bytes(),
+        Some(ext) if ext == "png" => {
+            let canvas = raster::rasterize(drawing_turtle, args.width, args.height, args.supersample);
+            png::encode(canvas.width(), canvas.height(), &canvas.to_rgb())
+        }
+        Some(ext) if ext == "gif" => {
+            //gifs store their size in 16 bits
+            if args.width > u16::MAX as usize || args.height > u16::MAX as usize {
+                return Err(format!(
+                    "Unable to write {path}: a gif can be at most {} pixels wide and high",
+                    u16::MAX
+                ));
+            }
+            gif::from_turtle(
                 drawing_turtle,
-            ),
-            ast::ASTnodeType::Container => unreachable!(), //should never happen. make this "a bug was found in the interpreter" error
+                raster::Canvas::new(args.width, args.height, args.supersample),
+                args.frame_segments,
+                args.frame_delay,
+                args.hold,
+            )
         }
+        _ => {
+            return Err(format!(
+                "Unable to write {path}: unknown output format (try .svg, .png or .gif)"
+            ));
+        }
+    };
+    fs::write(path, contents).map_err(|err| format!("Unable to write {path}: {err}"))
+}
+
+//writes or shows the drawing, depending on the arguments
+fn show(args: &Args, drawing_turtle: turtle::Turtle) -> Result<(), String> {
+    let written = match &args.output {
+        Some(path) => write_output(args, path, &drawing_turtle),
+        None => Ok(()),
+    };
+    if args.no_window {
+        print!("{}", drawing_turtle.summary());
+    } else if args.output.is_none() && drawing_turtle.should_render() {
+        #[cfg(feature = "viewer")]
+        drawing_turtle.render(if args.animate {
+            turtle::Playback::new(args.speed)
+        } else {
+            turtle::Playback::finished(args.speed)
+        });
+        #[cfg(not(feature = "viewer"))]
+        println!("drw was built without a window, so use -o or --no-window to see the drawing");
     }
+    written
 }
 
-fn main() {
-    let mut data_stack: stack::Stack<item::Item> = stack::Stack::new();
-    let mut dip_stack: stack::Stack<item::Item> = stack::Stack::new();
-    let mut drawing_turtle = turtle::Turtle::new();
-
-    let asts_to_pass = read();
-    if let Ok(asts_to_pass) = asts_to_pass {
-        let output = eval(
-            asts_to_pass.0,
-            asts_to_pass.1,
-            &mut data_stack,
-            &mut dip_stack,
-            &mut drawing_turtle,
-        );
-        println!("{}", output);
-        if drawing_turtle.should_render() {
-            drawing_turtle.render();
+//prints the report, failing if there were any warnings so it can be used before committing
+fn check(args: &Args, file: &str) -> ExitCode {
+    let mut program = readfile(file.to_string());
+    let _ = program.pop();
+    let report = load_modules(args).and_then(|mut interpreter| interpreter.check(file, &program));
+    match report {
+        Ok(report) => {
+            print!("{report}");
+            if report.warnings.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+        }
+        Err(err) => {
+            println!("{}", err);
+            ExitCode::FAILURE
         }
-    } else {
-        println!("{}", asts_to_pass.unwrap_err());
     }
 }
-fn dyadic_op(f: &dyn Fn(f64, f64) -> f64, default: f64, data_stack: &mut stack::Stack<item::Item>) {
-    let a = data_stack.pop().unwrap_or(item::Item::from_num(default));
-    let b = data_stack.pop().unwrap_or(item::Item::from_num(default));
-    data_stack.push(_apply_dyadic_op(f, a, b));
+
+fn help(glyph: Option<&str>) -> ExitCode {
+    let Some(glyph) = glyph else {
+        print!("{}", builtins::help_all());
+        return ExitCode::SUCCESS;
+    };
+    let mut chars = glyph.chars();
+    let text = match (chars.next(), chars.next()) {
+        (Some(c), None) => builtins::help(c),
+        _ => None,
+    };
+    match text {
+        Some(text) => {
+            print!("{text}");
+            ExitCode::SUCCESS
+        }
+        None => {
+            println!("`{glyph}` isn't a builtin. `drw help --all` lists every one");
+            ExitCode::FAILURE
+        }
+    }
 }
-//there is probably some optimization that could be done here because you can quicken somthing once you know one arg is numeric
-fn _apply_dyadic_op(f: &dyn Fn(f64, f64) -> f64, a: item::Item, b: item::Item) -> item::Item {
-    if a.itemtype == item::ItemType::Nil || b.itemtype == item::ItemType::Nil {
-        item::Item::nil()
-    } else {
-        if a.itemtype == item::ItemType::Number {
-            if b.itemtype == item::ItemType::Number {
-                //a and b are both numbers, so just normally apply
-                let result = f(a.get_number(), b.get_number());
-                //protect the user from NaNs and the like
-                if result.is_finite() {
-                    item::Item::from_num(result)
-                } else {
-                    item::Item::nil()
+
+//runs every test in dir, printing a diff for each one that doesn't match and a summary at the end
+fn run_tests(args: &Args, dir: &str, bless: bool) -> ExitCode {
+    let tests = match testing::find_tests(Path::new(dir)) {
+        Ok(tests) => tests,
+        Err(err) => {
+            println!("Unable to read {dir}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let (mut passed, mut failed) = (0, 0);
+    for test in &tests {
+        let expected_path = testing::expected_path(test);
+        let actual = match testing::run_test(test, |interpreter| set_up(args, interpreter)) {
+            Ok(actual) => actual,
+            Err(err) => {
+                println!("FAIL {}: {err}", test.display());
+                failed += 1;
+                continue;
+            }
+        };
+        if bless {
+            match fs::write(&expected_path, &actual) {
+                Ok(()) => println!("wrote {}", expected_path.display()),
+                Err(err) => {
+                    println!("Unable to write {}: {err}", expected_path.display());
+                    failed += 1;
                 }
-            } else {
-                //a is a number, b is a box so apply to all elements of b using this function
-                item::Item::from_box(item::DrwBox::new(
-                    _apply_dyadic_op(f, a.clone(), b.clone().get_box().r),
-                    _apply_dyadic_op(f, a.clone(), b.clone().get_box().g),
-                    _apply_dyadic_op(f, a.clone(), b.clone().get_box().b),
-                ))
             }
-        } else {
-            if b.itemtype == item::ItemType::Number {
-                //a is a box and b is a number, so do the same thing as (num, box)
-                item::Item::from_box(item::DrwBox::new(
-                    _apply_dyadic_op(f, a.clone().get_box().r, b.clone()),
-                    _apply_dyadic_op(f, a.clone().get_box().g, b.clone()),
-                    _apply_dyadic_op(f, a.clone().get_box().b, b.clone()),
-                ))
-            } else {
-                //both are boxes so ~~its scary~~ it applys each element to the one in the other arg
-                item::Item::from_box(item::DrwBox::new(
-                    _apply_dyadic_op(f, a.clone().get_box().r, b.clone().get_box().r),
-                    _apply_dyadic_op(f, a.clone().get_box().g, b.clone().get_box().g),
-                    _apply_dyadic_op(f, a.clone().get_box().b, b.clone().get_box().b),
-                ))
+            continue;
+        }
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => passed += 1,
+            Ok(expected) => {
+                println!("FAIL {}\n{}", test.display(), testing::diff(&expected, &actual));
+                failed += 1;
+            }
+            Err(_) => {
+                println!(
+                    "FAIL {}: there is no {} to check against (--bless writes one)",
+                    test.display(),
+                    expected_path.display()
+                );
+                failed += 1;
             }
         }
     }
+    if !bless {
+        println!("{passed} passed, {failed} failed");
+    }
+    if failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
 }
-fn monadic_op(f: &dyn Fn(f64) -> f64, default: f64, data_stack: &mut stack::Stack<item::Item>) {
-    let a = data_stack.pop().unwrap_or(item::Item::from_num(default));
-    data_stack.push(_apply_monadic_op(f, a));
-}
-fn _apply_monadic_op(f: &dyn Fn(f64) -> f64, a: item::Item) -> item::Item {
-    match a.itemtype {
-        item::ItemType::Number => {
-            let result = f(a.get_number());
-            if result.is_finite() {
-                item::Item::from_num(result)
-            } else {
-                item::Item::nil()
-            }
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if let Some(Command::Test { dir, bless }) = &args.command {
+        return run_tests(&args, dir, *bless);
+    }
+    if let Some(Command::Check { file }) = &args.command {
+        return check(&args, file);
+    }
+    if let Some(Command::Help { glyph, all }) = &args.command {
+        return help(if *all { None } else { glyph.as_deref() });
+    }
+    let Some(file) = &args.file else {
+        return repl(&args);
+    };
+    let mut interpreter = match load_modules(&args) {
+        Ok(interpreter) => interpreter,
+        Err(err) => {
+            println!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut program = readfile(file.to_string());
+    let _ = program.pop();
+    let exit_code = match interpreter.run(file, &program) {
+        Ok(()) => {
+            println!("{}", interpreter.stack_string());
+            ExitCode::SUCCESS
+        }
+        Err(err @ drw::Error::Parse(_)) => {
+            println!("{}", err);
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            println!("{}", err);
+            ExitCode::FAILURE
+        }
+    };
+    //whatever was drawn before an error is still worth seeing
+    match show(&args, interpreter.into_turtle()) {
+        Ok(()) => exit_code,
+        Err(err) => {
+            println!("{}", err);
+            ExitCode::FAILURE
         }
-        item::ItemType::Nil => item::Item::nil(),
-        item::ItemType::Box => item::Item::from_box(item::DrwBox::new(
-            _apply_monadic_op(f, a.clone().get_box().r),
-            _apply_monadic_op(f, a.clone().get_box().g),
-            _apply_monadic_op(f, a.clone().get_box().b),
-        )),
     }
 }
-fn comp_op(f: &dyn Fn(f64, f64) -> bool, data_stack: &mut stack::Stack<item::Item>) {
-    let a = data_stack.pop();
-    let b = data_stack.pop();
-    //comparison operators return false by default, which is why they are special cased
-    data_stack.push(match a {
-        Some(a_item) => match b {
-            Some(b_item) => {
-                //turn f into a regular function so it can be passed to _apply_dyadic_op
-                let f: &dyn Fn(f64, f64) -> f64 = &|a, b| if f(a, b) { 1.0 } else { 0.0 };
-                _apply_dyadic_op(f, a_item, b_item)
-            }
-            None => item::Item::from_num(0.0),
-        },
-        None => item::Item::from_num(0.0),
-    });
-}
 
-//like _apply_monadic_op, but only is nil and has no nil guards
-
-fn apply_is_nil(a: item::Item) -> item::Item {
-    match a.itemtype {
-        item::ItemType::Nil => item::Item::from_num(1.0),
-        item::ItemType::Number => item::Item::from_num(0.0),
-        item::ItemType::Box => item::Item::from_box(item::DrwBox::new(
-            apply_is_nil(a.clone().get_box().r),
-            apply_is_nil(a.clone().get_box().g),
-            apply_is_nil(a.clone().get_box().b),
-        )),
+#[test]
+fn timeout_test() {
+    let timeout = |value: &str| Args::try_parse_from(["drw", "--timeout", value]).map(|args| args.timeout);
+    assert_eq!(timeout("1.5").unwrap(), Some(Duration::from_millis(1500)));
+    for value in ["-1", "0", "nan", "inf", "1e30", "soon"] {
+        assert!(timeout(value).is_err(), "{value}");
     }
 }
@@ -0,0 +1,135 @@
+//finds the modules that `#import` lines and -m name: next to whatever imports them, in the folders on DRW_PATH,
+//then the ones built into drw, so the standard library works wherever drw is run from
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const IMPORT: &str = "#import ";
+const EXTENSION: &str = "drwm";
+
+//the lib folder, built in. the names are what `#import` takes
+const BUNDLED: &[(&str, &str)] = &[("list", include_str!("../lib/list.drwm"))];
+
+//a module that was found, ready to load
+#[derive(Clone, PartialEq, Debug)]
+pub struct Found {
+    pub name: String, //for error messages
+    pub key: String,  //the same for every way of naming the same module, so it is only loaded once
+    pub source: String,
+}
+
+//the folders on DRW_PATH, split the same way PATH is
+pub fn search_path_from_env() -> Vec<PathBuf> {
+    env::var_os("DRW_PATH")
+        .map(|paths| env::split_paths(&paths).filter(|dir| !dir.as_os_str().is_empty()).collect())
+        .unwrap_or_default()
+}
+
+//what an `#import` line asks for: the module, and any of its functions to load under new names
+#[derive(Clone, PartialEq, Debug)]
+pub struct Import<'a> {
+    pub name: &'a str,
+    pub renames: Vec<(char, char)>, //old name, new name
+}
+
+//reads an `#import` line, if it is one. renames are written `old→new` (or `old->new`) after the module
+pub fn parse_import(line: &str) -> Option<Import<'_>> {
+    let mut name = line.trim_start().strip_prefix(IMPORT)?.trim();
+    let mut renames = Vec::new();
+    //from the end, since the module's path can have spaces in it
+    while let Some((before, last)) = name.rsplit_once(char::is_whitespace)
+        && let Some(rename) = parse_rename(last)
+    {
+        renames.insert(0, rename);
+        name = before.trim_end();
+    }
+    if name.is_empty() {
+        return None;
+    }
+    Some(Import { name, renames })
+}
+
+fn parse_rename(word: &str) -> Option<(char, char)> {
+    match word.chars().collect::<Vec<char>>()[..] {
+        [old, '→', new] | [old, '-', '>', new] => Some((old, new)),
+        _ => None,
+    }
+}
+
+//`name` can be a path, or a module name without the .drwm
+pub fn find(name: &str, from_dir: &Path, search_path: &[PathBuf]) -> Result<Found, String> {
+    let mut file = PathBuf::from(name);
+    if file.extension().is_none() {
+        file.set_extension(EXTENSION);
+    }
+    let dirs: Vec<&Path> = std::iter::once(from_dir)
+        .chain(search_path.iter().map(PathBuf::as_path))
+        .collect();
+    for dir in &dirs {
+        let path = dir.join(&file);
+        if let Ok(source) = fs::read_to_string(&path) {
+            let key = fs::canonicalize(&path).unwrap_or(path.clone());
+            return Ok(Found {
+                name: path.display().to_string(),
+                key: key.display().to_string(),
+                source,
+            });
+        }
+    }
+    let stem = name.strip_suffix(".drwm").unwrap_or(name);
+    if let Some((bundled_name, source)) = BUNDLED.iter().find(|(bundled_name, _)| *bundled_name == stem) {
+        let name = format!("<lib>/{bundled_name}.{EXTENSION}");
+        return Ok(Found {
+            key: name.clone(),
+            name,
+            source: source.to_string(),
+        });
+    }
+    let looked_in: Vec<String> = dirs
+        .iter()
+        .map(|dir| match dir.as_os_str().is_empty() {
+            true => ".".to_string(),
+            false => dir.display().to_string(),
+        })
+        .collect();
+    Err(format!(
+        "Unable to find the module `{name}`. Looked for {} in {} and in the modules built into drw",
+        file.display(),
+        looked_in.join(", ")
+    ))
+}
+
+#[test]
+fn parse_import_test() {
+    let name = |line| parse_import(line).map(|import| import.name);
+    assert_eq!(name("#import list"), Some("list"));
+    assert_eq!(name("  #import ../my lib/shapes.drwm "), Some("../my lib/shapes.drwm"));
+    assert_eq!(name("#import "), None);
+    assert_eq!(name("#imports list"), None);
+    assert_eq!(name("1 2+ #import list"), None);
+    assert_eq!(
+        parse_import("#import my lib/geometry ⊂→⊆ ⊃->⊇"),
+        Some(Import {
+            name: "my lib/geometry",
+            renames: vec![('⊂', '⊆'), ('⊃', '⊇')]
+        })
+    );
+    assert_eq!(name("#import ⊂→⊆"), Some("⊂→⊆")); //a module has to come first
+}
+
+#[test]
+fn find_test() {
+    let dir = env::temp_dir().join(format!("drw_find_test_{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub").join("shapes.drwm"), "□_4[.^90~]◌").unwrap();
+    let found = find("shapes", &dir, &[dir.join("sub")]).unwrap();
+    assert_eq!(found.source, "□_4[.^90~]◌");
+    assert_eq!(find("sub/shapes.drwm", &dir, &[]).unwrap().key, found.key);
+    //the built in list module is only used when there isn't one on the path
+    assert_eq!(find("list", &dir, &[]).unwrap().name, "<lib>/list.drwm");
+    fs::write(dir.join("list.drwm"), "").unwrap();
+    assert_eq!(find("list", &dir, &[]).unwrap().source, "");
+    assert!(find("nope", &dir, &[]).unwrap_err().contains("nope.drwm"));
+    fs::remove_dir_all(&dir).unwrap();
+}
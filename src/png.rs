@@ -0,0 +1,165 @@
+//a minimal png encoder: 8 bit rgb, one idat chunk, fixed huffman deflate with run length matches
+//turtle drawings are mostly long runs of background, so this compresses them well enough without pulling in a crate
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            out: Vec::new(),
+            acc: 0,
+            bits: 0,
+        }
+    }
+    //deflate packs ordinary values least significant bit first
+    fn write(&mut self, value: u32, count: u32) {
+        self.acc |= value << self.bits;
+        self.bits += count;
+        while self.bits >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+    //but huffman codes most significant bit first
+    fn write_code(&mut self, code: u32, count: u32) {
+        self.write(code.reverse_bits() >> (32 - count), count);
+    }
+    fn write_symbol(&mut self, symbol: u16) {
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol as u32, 8),
+            144..=255 => self.write_code(0x190 + (symbol - 144) as u32, 9),
+            256..=279 => self.write_code((symbol - 256) as u32, 7),
+            _ => self.write_code(0xc0 + (symbol - 280) as u32, 8),
+        }
+    }
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    writer.write(1, 1); //final block
+    writer.write(1, 2); //fixed huffman codes
+    let mut idx = 0;
+    while idx < data.len() {
+        writer.write_symbol(data[idx] as u16);
+        idx += 1;
+        //copy the previous byte as long as it keeps repeating (a match with distance 1)
+        loop {
+            let mut run = 0;
+            while run < 258 && idx + run < data.len() && data[idx + run] == data[idx - 1] {
+                run += 1;
+            }
+            if run < 3 {
+                break;
+            }
+            let code = LENGTH_BASE.iter().rposition(|base| *base as usize <= run).unwrap();
+            writer.write_symbol(257 + code as u16);
+            writer.write((run - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+            writer.write_code(0, 5); //distance code 0 is a distance of 1
+            idx += run;
+        }
+    }
+    writer.write_symbol(256); //end of block
+    writer.finish()
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        //5552 is the most bytes that can be summed before b could overflow
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn push_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+//rgb is packed rows of 3 bytes per pixel
+pub fn encode(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    let mut header = Vec::new();
+    header.extend((width as u32).to_be_bytes());
+    header.extend((height as u32).to_be_bytes());
+    header.extend([8, 2, 0, 0, 0]); //8 bit depth, rgb, deflate, standard filters, no interlace
+    push_chunk(&mut out, b"IHDR", &header);
+
+    //filter every row with "sub" so flat colors turn into runs of zeros
+    let mut filtered = Vec::with_capacity((width * 3 + 1) * height);
+    for row in rgb.chunks(width * 3).take(height) {
+        filtered.push(1);
+        for (i, byte) in row.iter().enumerate() {
+            let left = if i >= 3 { row[i - 3] } else { 0 };
+            filtered.push(byte.wrapping_sub(left));
+        }
+    }
+    let mut zlib = vec![0x78, 0x01];
+    zlib.extend(deflate(&filtered));
+    zlib.extend(adler32(&filtered).to_be_bytes());
+    push_chunk(&mut out, b"IDAT", &zlib);
+    push_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+#[test]
+fn crc32_test() {
+    assert_eq!(crc32(b"IEND"), 0xae426082);
+    assert_eq!(crc32(b"123456789"), 0xcbf43926);
+}
+
+#[test]
+fn adler32_test() {
+    assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+}
+
+#[test]
+fn encode_test() {
+    let png = encode(2, 1, &[255, 0, 0, 0, 0, 255]);
+    assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+}
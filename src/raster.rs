@@ -0,0 +1,199 @@
+//a tiny software rasterizer for the turtle's history, so drawings can be exported without a window or a gpu
+//every segment is drawn as a capsule (a line with round ends), the same as the raylib viewer draws it
+
+use crate::turtle;
+
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    supersample: usize,
+    scale: f32,
+    offset: turtle::Point,
+    pixels: Vec<[u8; 3]>, //at supersampled resolution
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, supersample: usize) -> Self {
+        let supersample = supersample.max(1);
+        //fit the window's coordinate space into the image, keeping its aspect ratio
+        let scale = (width as f32 / turtle::WINDOW_WIDTH as f32)
+            .min(height as f32 / turtle::WINDOW_HEIGHT as f32);
+        let offset = turtle::Point::new(
+            (width as f32 - turtle::WINDOW_WIDTH as f32 * scale) / 2.0,
+            (height as f32 - turtle::WINDOW_HEIGHT as f32 * scale) / 2.0,
+        );
+        Canvas {
+            width,
+            height,
+            supersample,
+            scale,
+            offset,
+            pixels: vec![[0, 0, 0]; width * supersample * height * supersample],
+        }
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    fn to_subpixel(&self, p: &turtle::Point) -> (f32, f32) {
+        let ss = self.supersample as f32;
+        (
+            (p.x * self.scale + self.offset.x) * ss,
+            (p.y * self.scale + self.offset.y) * ss,
+        )
+    }
+    pub fn draw_frame(&mut self, frame: &turtle::TurtleHistoryFrame) {
+        if let Some(fill) = &frame.fill {
+            self.draw_fill(fill, &frame.color);
+            return;
+        }
+        if frame.pen_size <= 0.0 {
+            return; //pen was up
+        }
+        let (ax, ay) = self.to_subpixel(&frame.start_pos);
+        let (bx, by) = self.to_subpixel(&frame.end_pos);
+        let ss = self.supersample as f32;
+        //never let a line get thinner than one output pixel or it can vanish between pixel centers
+        let radius = (frame.pen_size * self.scale / 2.0).max(0.5) * ss;
+        let color = [frame.color.r, frame.color.g, frame.color.b];
+        let full_width = self.width * self.supersample;
+        let full_height = self.height * self.supersample;
+        let min_x = (ax.min(bx) - radius).floor().max(0.0) as usize;
+        let min_y = (ay.min(by) - radius).floor().max(0.0) as usize;
+        let max_x = ((ax.max(bx) + radius).ceil().max(0.0) as usize).min(full_width);
+        let max_y = ((ay.max(by) + radius).ceil().max(0.0) as usize).min(full_height);
+        let (dx, dy) = (bx - ax, by - ay);
+        let length_squared = dx * dx + dy * dy;
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                //distance from the pixel center to the closest point on the segment
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let t = if length_squared == 0.0 {
+                    0.0
+                } else {
+                    (((px - ax) * dx + (py - ay) * dy) / length_squared).clamp(0.0, 1.0)
+                };
+                let (cx, cy) = (ax + t * dx - px, ay + t * dy - py);
+                if cx * cx + cy * cy <= radius * radius {
+                    self.pixels[y * full_width + x] = color;
+                }
+            }
+        }
+    }
+    fn draw_fill(&mut self, fill: &turtle::Fill, color: &turtle::TurtleColor) {
+        //move the shape into subpixels so it can be filled one subpixel row at a time
+        let points = fill
+            .points
+            .iter()
+            .map(|p| {
+                let (x, y) = self.to_subpixel(p);
+                turtle::Point::new(x, y)
+            })
+            .collect();
+        let fill = turtle::Fill {
+            points,
+            rule: fill.rule.clone(),
+        };
+        let full_width = self.width * self.supersample;
+        let full_height = self.height * self.supersample;
+        let (top, bottom) = fill.vertical_range();
+        let top = top.floor().max(0.0) as usize;
+        let bottom = (bottom.ceil().max(0.0) as usize).min(full_height);
+        for y in top..bottom {
+            for (left, right) in fill.spans(y as f32 + 0.5) {
+                //a pixel is inside when its center is
+                let left = (left - 0.5).ceil().max(0.0) as usize;
+                let right = ((right - 0.5).ceil().max(0.0) as usize).min(full_width);
+                for x in left..right {
+                    self.pixels[y * full_width + x] = [color.r, color.g, color.b];
+                }
+            }
+        }
+    }
+    //averages each supersample block down to one pixel, giving packed rgb rows
+    pub fn to_rgb(&self) -> Vec<u8> {
+        let ss = self.supersample;
+        let full_width = self.width * ss;
+        let mut out = Vec::with_capacity(self.width * self.height * 3);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut sum = [0u32; 3];
+                for sy in 0..ss {
+                    for sx in 0..ss {
+                        let pixel = self.pixels[(y * ss + sy) * full_width + x * ss + sx];
+                        for channel in 0..3 {
+                            sum[channel] += pixel[channel] as u32;
+                        }
+                    }
+                }
+                for channel in sum {
+                    out.push(((channel + (ss * ss) as u32 / 2) / (ss * ss) as u32) as u8);
+                }
+            }
+        }
+        out
+    }
+}
+
+pub fn rasterize(
+    drawing_turtle: &turtle::Turtle,
+    width: usize,
+    height: usize,
+    supersample: usize,
+) -> Canvas {
+    let mut canvas = Canvas::new(width, height, supersample);
+    for frame in drawing_turtle.history() {
+        canvas.draw_frame(frame);
+    }
+    canvas
+}
+
+#[test]
+fn rasterize_test() {
+    let mut t = turtle::Turtle::new();
+    t.set_color(255, 0, 0);
+    t.forward(100.0);
+    t.push();
+    t.pen_up();
+    t.turn(90.0);
+    t.forward(100.0);
+    t.push();
+    let rgb = rasterize(&t, 800, 450, 1).to_rgb();
+    let pixel = |x: usize, y: usize| &rgb[(y * 800 + x) * 3..(y * 800 + x) * 3 + 3];
+    assert_eq!(pixel(450, 225), [255, 0, 0]);
+    assert_eq!(pixel(450, 240), [0, 0, 0]);
+    assert_eq!(pixel(500, 275), [0, 0, 0]); //drawn with the pen up
+    //doubling the size doubles the drawing, and supersampling blends the edges
+    let rgb = rasterize(&t, 1600, 900, 4).to_rgb();
+    let pixel = |x: usize, y: usize| &rgb[(y * 1600 + x) * 3..(y * 1600 + x) * 3 + 3];
+    assert_eq!(pixel(900, 450), [255, 0, 0]);
+    assert_eq!(pixel(1000, 460), [0, 0, 0]);
+}
+
+#[test]
+fn fill_test() {
+    //a square with a smaller square inside, wound the same way, so only even-odd leaves a hole
+    for (rule, middle) in [
+        (turtle::FillRule::NonZero, [0, 255, 0]),
+        (turtle::FillRule::EvenOdd, [0, 0, 0]),
+    ] {
+        let mut t = turtle::Turtle::new();
+        t.set_color(0, 255, 0);
+        t.pen_up();
+        t.begin_fill();
+        for (x, y) in [(500.0, 225.0), (500.0, 325.0), (400.0, 325.0), (400.0, 225.0)] {
+            t.jump(turtle::Point::new(x, y));
+        }
+        for (x, y) in [(425.0, 250.0), (475.0, 250.0), (475.0, 300.0), (425.0, 300.0), (425.0, 250.0)] {
+            t.jump(turtle::Point::new(x, y));
+        }
+        t.end_fill(rule);
+        let rgb = rasterize(&t, 800, 450, 1).to_rgb();
+        let pixel = |x: usize, y: usize| &rgb[(y * 800 + x) * 3..(y * 800 + x) * 3 + 3];
+        assert_eq!(pixel(410, 235), [0, 255, 0]);
+        assert_eq!(pixel(450, 275), middle);
+        assert_eq!(pixel(390, 275), [0, 0, 0]);
+    }
+}
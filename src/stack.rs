@@ -1,3 +1,5 @@
+use std::fmt;
+
 pub struct Stack<T> {
     stack: Vec<T>,
 }
@@ -15,6 +17,7 @@ where
     pub fn pop(&mut self) -> Option<T> {
         self.stack.pop()
     }
+    #[cfg(test)]
     pub fn is_empty(&self) -> bool {
         self.stack.is_empty()
     }
@@ -22,6 +25,10 @@ where
     pub fn length(&self) -> usize {
         self.stack.len()
     }
+    //bottom first
+    pub fn items(&self) -> &[T] {
+        &self.stack
+    }
     pub fn peek(&self) -> Option<&T> {
         self.stack.last()
     }
@@ -37,21 +44,29 @@ where
     pub fn dup(&mut self, default: T) {
         self.push(self.peek().unwrap_or(&default).clone())
     }
-    pub fn to_string(&mut self) -> String {
-        let mut out = "bottom\n".to_string();
+}
+
+impl<T: Clone + ToString> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Stack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "bottom")?;
         for i in &self.stack {
-            out += &(i.to_string() + "\n");
+            writeln!(f, "{i}")?;
         }
-        return out + "top\n";
+        writeln!(f, "top")
     }
 }
 #[test]
 fn push_pop_test() {
     let mut a: Stack<u8> = Stack::new();
-    let o;
     a.push(2);
     a.push(1);
-    o = a.pop();
+    let o = a.pop();
     assert_eq!(o.unwrap(), 1);
     assert_eq!(a.pop().unwrap(), 2);
 }
@@ -86,11 +101,11 @@ fn dip_test() {
     let mut b: Stack<u8> = Stack::new();
     a.push(3);
     a.push(1);
-    a.dip(&mut b);
+    a.dip(&mut b, 0);
     assert_eq!(*b.peek().unwrap(), 1);
     assert_eq!(*a.peek().unwrap(), 3);
     a.push(2);
-    b.dip(&mut a);
+    b.dip(&mut a, 0);
     assert_eq!(b.length() as u8, 0);
     assert_eq!(a.pop().unwrap(), 1);
     assert_eq!(a.pop().unwrap(), 2);
@@ -101,7 +116,7 @@ fn swap_test() {
     let mut a: Stack<u8> = Stack::new();
     a.push(1);
     a.push(2);
-    a.swap();
+    a.swap(0);
     assert_eq!(a.pop().unwrap(), 1);
     assert_eq!(a.pop().unwrap(), 2);
 }
@@ -109,7 +124,7 @@ fn swap_test() {
 fn dup_test() {
     let mut a: Stack<u8> = Stack::new();
     a.push(1);
-    a.dup();
+    a.dup(0);
     assert_eq!(a.pop().unwrap(), 1);
     assert_eq!(a.pop().unwrap(), 1);
 }
@@ -118,6 +133,5 @@ fn to_string_test() {
     let mut a: Stack<u8> = Stack::new();
     a.push(1);
     a.push(2);
-    println!("{}", a.to_string());
     assert_eq!(a.to_string(), "bottom\n1\n2\ntop\n".to_string());
 }
@@ -0,0 +1,83 @@
+//writes the turtle's history out as an svg document, one <line> per frame and one <path> per fill
+//the viewbox is the same size as the raylib window so drawings look the same in both
+
+use crate::turtle;
+
+pub fn from_turtle(drawing_turtle: &turtle::Turtle) -> String {
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+        turtle::WINDOW_WIDTH,
+        turtle::WINDOW_HEIGHT
+    );
+    //the window clears to black, so the file should too
+    out += "<rect width=\"100%\" height=\"100%\" fill=\"black\"/>\n";
+    for frame in drawing_turtle.history() {
+        if let Some(fill) = &frame.fill {
+            let points: Vec<String> = fill.points.iter().map(|p| format!("{} {}", p.x, p.y)).collect();
+            out += &format!(
+                "<path d=\"M {} Z\" fill=\"rgb({},{},{})\" fill-rule=\"{}\"/>\n",
+                points.join(" L "),
+                frame.color.r,
+                frame.color.g,
+                frame.color.b,
+                match fill.rule {
+                    turtle::FillRule::NonZero => "nonzero",
+                    turtle::FillRule::EvenOdd => "evenodd",
+                },
+            );
+            continue;
+        }
+        if frame.pen_size <= 0.0 {
+            continue; //pen was up, nothing was drawn
+        }
+        out += &format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"rgb({},{},{})\" stroke-width=\"{}\" stroke-linecap=\"round\"/>\n",
+            frame.start_pos.x,
+            frame.start_pos.y,
+            frame.end_pos.x,
+            frame.end_pos.y,
+            frame.color.r,
+            frame.color.g,
+            frame.color.b,
+            frame.pen_size,
+        );
+    }
+    out + "</svg>\n"
+}
+
+#[test]
+fn from_turtle_test() {
+    let mut t = turtle::Turtle::new();
+    t.forward(10.0);
+    t.push();
+    t.pen_up();
+    t.forward(10.0);
+    t.push();
+    t.pen_down();
+    t.set_color(255, 0, 0);
+    t.set_pen_size(3.0);
+    t.forward(10.0);
+    t.push();
+    let svg = from_turtle(&t);
+    assert_eq!(svg.matches("<line").count(), 2); //the pen up frame is left out
+    assert!(svg.contains(
+        "<line x1=\"420\" y1=\"225\" x2=\"430\" y2=\"225\" stroke=\"rgb(255,0,0)\" stroke-width=\"3\" stroke-linecap=\"round\"/>"
+    ));
+    assert!(svg.ends_with("</svg>\n"));
+}
+
+#[test]
+fn fill_test() {
+    let mut t = turtle::Turtle::new();
+    t.begin_fill();
+    t.forward(10.0);
+    t.push();
+    t.turn(90.0);
+    t.push();
+    t.end_fill(turtle::FillRule::EvenOdd);
+    let svg = from_turtle(&t);
+    assert!(svg.contains(
+        "<path d=\"M 400 225 L 410 225 L 410 235 Z\" fill=\"rgb(255,255,255)\" fill-rule=\"evenodd\"/>"
+    ));
+    assert_eq!(svg.matches("<line").count(), 2); //the fill itself draws no line
+}
@@ -0,0 +1,115 @@
+//runs .drw files and compares what they leave behind with the .expected files next to them, for `drw test`
+
+use crate::interpreter::{Error, Interpreter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+//every .drw file in a folder, sorted so the summary comes out the same each time
+pub fn find_tests(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut tests: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "drw"))
+        .collect();
+    tests.sort();
+    Ok(tests)
+}
+
+pub fn expected_path(test: &Path) -> PathBuf {
+    test.with_extension("expected")
+}
+
+//runs a test and describes everything it is checked on: what `?` printed, any error, the final stack and the drawing.
+//setup gets each fresh interpreter first, to pass on limits like --max-steps and load the modules from -m
+pub fn run_test(test: &Path, setup: impl Fn(&mut Interpreter) -> Result<(), Error>) -> io::Result<String> {
+    let source = fs::read_to_string(test)?;
+    let dir = test.parent().unwrap_or(Path::new(""));
+    //only the file's name, so the errors read the same whichever folder the tests were run from
+    let name = test.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_folder(&name, dir);
+    interpreter.keep_printed();
+    let result = setup(&mut interpreter)
+        .and_then(|()| interpreter.run(&name, source.strip_suffix('\n').unwrap_or(&source)));
+    let mut out = String::new();
+    let printed = interpreter.take_printed();
+    if !printed.is_empty() {
+        out += "printed:\n";
+        out += &printed;
+    }
+    if let Err(err) = result {
+        out += &format!("{err}\n");
+    }
+    out += "stack:\n";
+    out += &interpreter.stack_string();
+    out += "segments:\n";
+    out += &interpreter.turtle().dump();
+    Ok(out)
+}
+
+//the lines of expected and actual, with - in front of the ones only expected has and + for the ones only actual has
+pub fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    //common[i][j] is how long the longest common run of lines is from old[i..] and new[j..]
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = String::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            out += &format!("  {}\n", old[i]);
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            out += &format!("- {}\n", old[i]);
+            i += 1;
+        } else {
+            out += &format!("+ {}\n", new[j]);
+            j += 1;
+        }
+    }
+    out
+}
+
+#[test]
+fn diff_test() {
+    assert_eq!(diff("a\nb\nc\n", "a\nb\nc\n"), "  a\n  b\n  c\n");
+    assert_eq!(diff("a\nb\nc\n", "a\nx\nc\nd\n"), "  a\n- b\n+ x\n  c\n+ d\n");
+}
+
+#[test]
+fn tests_folder_test() {
+    //every test in tests/ should still do what its .expected file says. `drw test --bless` writes them again
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    for test in find_tests(&dir).unwrap() {
+        let actual = run_test(&test, |interpreter| {
+            interpreter.set_max_steps(Some(1_000_000));
+            Ok(())
+        })
+        .unwrap();
+        let expected = fs::read_to_string(expected_path(&test)).unwrap_or_default();
+        assert!(actual == expected, "{} changed:\n{}", test.display(), diff(&expected, &actual));
+    }
+}
+
+#[test]
+fn test_setup_test() {
+    let dir = std::env::temp_dir().join(format!("drw_test_setup_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("near.drwm"), "N_7").unwrap();
+    let test = dir.join("uses.drw");
+    fs::write(&test, "#import near\nN 3⇡Σ\n").unwrap();
+    //near is found next to the test whatever the current folder is, and ⇡ and Σ come from the module setup loads, like -m
+    let actual = run_test(&test, |interpreter| interpreter.import("list")).unwrap();
+    assert_eq!(actual, "stack:\nbottom\n7\n6\ntop\nsegments:\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
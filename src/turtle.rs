@@ -1,14 +1,15 @@
 //a simple, hand rolled turtle library. Its much faster and more memory effincent then the normal rust turtle
 //not very flexible but great for drw
 
+#[cfg(feature = "viewer")]
 use raylib::prelude::*;
 
-const WINDOW_WIDTH: i32 = 800;
-const WINDOW_HEIGHT: i32 = 450;
-const THE_NUMBER_OF_RADIANS_IN_A_CIRCLE: f32 = 6.283185307179586;
+pub const WINDOW_WIDTH: i32 = 800;
+pub const WINDOW_HEIGHT: i32 = 450;
+const THE_NUMBER_OF_RADIANS_IN_A_CIRCLE: f32 = std::f32::consts::TAU;
 const THE_NUMBER_OF_DEGREES_IN_A_CIRCLE: f32 = 360.0;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TurnModes {
     DEGREE,
     RADIAN,
@@ -16,39 +17,54 @@ pub enum TurnModes {
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Point {
-    x: f32,
-    y: f32,
+    pub x: f32,
+    pub y: f32,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct TurtleColor {
-    r: u8,
-    g: u8,
-    b: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct TurtleHistoryFrame {
-    start_pos: Point,
-    end_pos: Point,
-    color: TurtleColor,
-    pen_size: f32,
+    pub start_pos: Point,
+    pub end_pos: Point,
+    pub color: TurtleColor,
+    pub pen_size: f32, //0 when the pen was up
+    pub fill: Option<Fill>, //set on the frame that ends a fill, which draws no line itself
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fill {
+    pub points: Vec<Point>,
+    pub rule: FillRule,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Turtle {
     history: Vec<TurtleHistoryFrame>,
     curr: TurtleHistoryFrame,
-    direction: f32,
+    direction: f32, //in degrees, whatever the turn mode is, so reading it back doesn't drift
     travel_dist: f32,
     turn_mode: TurnModes,
     pen_state: bool,
+    fill_points: Option<Vec<Point>>, //the points visited since the fill began
 }
 
 impl Point {
     pub fn new(newx: f32, newy: f32) -> Self {
         Point { x: newx, y: newy }
     }
+    #[cfg(feature = "viewer")]
     pub fn to_vector2(&self) -> Vector2 {
         Vector2::new(self.x, self.y)
     }
@@ -62,11 +78,18 @@ impl TurtleColor {
             b: newb,
         }
     }
+    #[cfg(feature = "viewer")]
     pub fn to_raycolor(&self) -> Color {
         Color::new(self.r, self.g, self.b, 255)
     }
 }
 
+impl Default for Turtle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Turtle {
     pub fn new() -> Self {
         Turtle {
@@ -76,11 +99,13 @@ impl Turtle {
                 end_pos: Point::new((WINDOW_WIDTH as f32) / 2.0, (WINDOW_HEIGHT as f32) / 2.0),
                 color: TurtleColor::new(255, 255, 255),
                 pen_size: 1.0,
+                fill: None,
             },
             direction: 0.0,
             travel_dist: 0.0,
             turn_mode: TurnModes::DEGREE,
             pen_state: true,
+            fill_points: None,
         }
     }
     pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
@@ -93,10 +118,31 @@ impl Turtle {
         self.turn_mode = m;
     }
     pub fn turn(&mut self, mut angle: f32) {
-        if self.using_degrees() {
-            angle = Self::deg_to_rad(angle)
+        if self.using_radians() {
+            angle = Self::rad_to_deg(angle)
+        }
+        self.direction += angle
+    }
+    //in the current turn mode, between 0 and a full circle
+    pub fn heading(&self) -> f32 {
+        let heading = self.direction.rem_euclid(THE_NUMBER_OF_DEGREES_IN_A_CIRCLE);
+        if self.using_radians() {
+            Self::deg_to_rad(heading)
+        } else {
+            heading
         }
-        self.direction = self.direction.clone() + angle
+    }
+    pub fn set_heading(&mut self, angle: f32) {
+        self.direction = 0.0;
+        self.turn(angle);
+    }
+    pub fn position(&self) -> Point {
+        self.curr.start_pos.clone()
+    }
+    //moves straight to a point, drawing a line there if the pen is down
+    pub fn jump(&mut self, to: Point) {
+        self.curr.end_pos = to;
+        self.record();
     }
     pub fn forward(&mut self, amount: f32) {
         self.travel_dist = amount;
@@ -116,65 +162,327 @@ impl Turtle {
     pub fn deg_to_rad(x: f32) -> f32 {
         x * (THE_NUMBER_OF_RADIANS_IN_A_CIRCLE / THE_NUMBER_OF_DEGREES_IN_A_CIRCLE)
     }
+    pub fn rad_to_deg(x: f32) -> f32 {
+        x * (THE_NUMBER_OF_DEGREES_IN_A_CIRCLE / THE_NUMBER_OF_RADIANS_IN_A_CIRCLE)
+    }
     pub fn polar_to_rect(r: f32, theta: f32) -> Point {
         let newx = theta.cos() * r;
         let newy = theta.sin() * r;
-        return Point::new(newx, newy);
+        Point::new(newx, newy)
     }
     pub fn push(&mut self) {
         //convert direction+travel dist to x,y translation
-        let translation = Self::polar_to_rect(self.travel_dist, self.direction);
+        let translation =
+            Self::polar_to_rect(self.travel_dist, Self::deg_to_rad(self.direction));
         self.curr.end_pos = Point::new(
             self.curr.start_pos.x + translation.x,
             self.curr.start_pos.y + translation.y,
         );
+        self.record();
+    }
+    //adds the current frame to the history and starts the next one where it ended
+    fn record(&mut self) {
         let mut new = self.curr.clone();
         if !self.pen_state {
             new.pen_size = 0.0;
         }
         self.history.push(new);
+        if let Some(points) = &mut self.fill_points {
+            points.push(self.curr.end_pos.clone());
+        }
         self.curr = TurtleHistoryFrame {
             start_pos: self.curr.clone().end_pos, //make the new start pos the new end pos
             end_pos: Point::new(0.0, 0.0),        //placeholder
             color: self.curr.clone().color,       //move color forward
             pen_size: self.curr.clone().pen_size, //move pen size forward
+            fill: None,
         }
     }
+    //starts remembering every point the turtle goes to, beginning with where it is now
+    pub fn begin_fill(&mut self) {
+        self.fill_points = Some(vec![self.position()]);
+    }
+    //closes the shape back to where the fill began and fills it with the current color
+    pub fn end_fill(&mut self, rule: FillRule) {
+        let Some(points) = self.fill_points.take() else {
+            return;
+        };
+        if points.len() < 3 {
+            return; //too few points to have an inside
+        }
+        self.history.push(TurtleHistoryFrame {
+            start_pos: self.position(),
+            end_pos: self.position(),
+            color: self.curr.color.clone(),
+            pen_size: 0.0,
+            fill: Some(Fill { points, rule }),
+        });
+    }
+    pub fn history(&self) -> &Vec<TurtleHistoryFrame> {
+        &self.history
+    }
+    //a short text description of what was drawn, for checking drawings without looking at them
+    pub fn summary(&self) -> String {
+        let drawn: Vec<&TurtleHistoryFrame> =
+            self.history.iter().filter(|frame| frame.pen_size > 0.0).collect();
+        let fills: Vec<&Fill> = self.history.iter().filter_map(|frame| frame.fill.as_ref()).collect();
+        let mut out = format!("segments: {}\n", drawn.len());
+        if !fills.is_empty() {
+            out += &format!("fills: {}\n", fills.len());
+        }
+        let mut points = drawn
+            .iter()
+            .flat_map(|frame| [&frame.start_pos, &frame.end_pos])
+            .chain(fills.iter().flat_map(|fill| &fill.points));
+        let Some(first) = points.next() else {
+            return out + "bounding box: none\n";
+        };
+        let (mut min, mut max) = (first.clone(), first.clone());
+        for pos in points {
+            min = Point::new(min.x.min(pos.x), min.y.min(pos.y));
+            max = Point::new(max.x.max(pos.x), max.y.max(pos.y));
+        }
+        out + &format!(
+            "bounding box: ({}, {}) to ({}, {})\n",
+            min.x.round(),
+            min.y.round(),
+            max.x.round(),
+            max.y.round()
+        )
+    }
+    //every segment and fill on its own line, rounded so tiny float differences don't show, for comparing drawings
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        for frame in &self.history {
+            let color = &frame.color;
+            if let Some(fill) = &frame.fill {
+                let rule = match fill.rule {
+                    FillRule::NonZero => "nonzero",
+                    FillRule::EvenOdd => "evenodd",
+                };
+                out += &format!("fill {rule} {} {} {}:", color.r, color.g, color.b);
+                for point in &fill.points {
+                    out += &format!(" {}", dump_point(point));
+                }
+                out.push('\n');
+            } else if frame.pen_size > 0.0 {
+                out += &format!(
+                    "{} to {} color {} {} {} size {}\n",
+                    dump_point(&frame.start_pos),
+                    dump_point(&frame.end_pos),
+                    color.r,
+                    color.g,
+                    color.b,
+                    dump_number(frame.pen_size)
+                );
+            }
+        }
+        out
+    }
     pub fn should_render(&self) -> bool {
-        self.history.len() != 0
+        !self.history.is_empty()
     }
-    pub fn render(self) {
+    #[cfg(feature = "viewer")]
+    pub fn render(self, mut playback: Playback) {
         let (mut rl, thread) = raylib::init()
             .size(WINDOW_WIDTH, WINDOW_HEIGHT)
             .title("drw output window")
             .resizable()
             .vsync()
             .build();
+        let total = self.history.len();
         while !rl.window_should_close() {
+            if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+                playback.toggle_pause();
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+                playback.step(1.0, total);
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_LEFT) {
+                playback.step(-1.0, total);
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_UP) {
+                playback.change_speed(2.0);
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_DOWN) {
+                playback.change_speed(0.5);
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_R) {
+                playback.restart();
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_END) {
+                playback.finish(total);
+            }
+            playback.advance(rl.get_frame_time(), total);
             let mut d = rl.begin_drawing(&thread);
             {
                 d.clear_background(Color::BLACK);
-                for frame in &self.history {
-                    d.draw_circle_v(
-                        frame.start_pos.to_vector2(),
-                        frame.pen_size / 2.0,
-                        frame.color.to_raycolor(),
-                    );
-                    d.draw_line_ex(
-                        frame.start_pos.to_vector2(),
-                        frame.end_pos.to_vector2(),
-                        frame.pen_size,
-                        frame.color.to_raycolor(),
+                let shown = playback.shown(total);
+                for frame in &self.history[..shown.0] {
+                    Self::draw_frame(&mut d, frame);
+                }
+                if let Some(frame) = self.history.get(shown.0) {
+                    //the segment being drawn right now
+                    let mut partial = frame.clone();
+                    partial.end_pos = Point::new(
+                        frame.start_pos.x + (frame.end_pos.x - frame.start_pos.x) * shown.1,
+                        frame.start_pos.y + (frame.end_pos.y - frame.start_pos.y) * shown.1,
                     );
-                    d.draw_circle_v(
-                        frame.end_pos.to_vector2(),
-                        frame.pen_size / 2.0,
+                    Self::draw_frame(&mut d, &partial);
+                    d.draw_circle_v(partial.end_pos.to_vector2(), 4.0, Color::GRAY);
+                }
+                if shown.0 < total || playback.paused {
+                    d.draw_text(&playback.status(total), 10, 10, 20, Color::GRAY);
+                }
+            };
+        }
+    }
+    #[cfg(feature = "viewer")]
+    fn draw_frame(d: &mut RaylibDrawHandle, frame: &TurtleHistoryFrame) {
+        if let Some(fill) = &frame.fill {
+            //raylib only fills convex shapes, so fill one row of pixels at a time instead
+            let (top, bottom) = fill.vertical_range();
+            for y in top.floor() as i32..bottom.ceil() as i32 {
+                for (left, right) in fill.spans(y as f32 + 0.5) {
+                    d.draw_rectangle_v(
+                        Vector2::new(left, y as f32),
+                        Vector2::new(right - left, 1.0),
                         frame.color.to_raycolor(),
                     );
                 }
+            }
+            return;
+        }
+        d.draw_circle_v(
+            frame.start_pos.to_vector2(),
+            frame.pen_size / 2.0,
+            frame.color.to_raycolor(),
+        );
+        d.draw_line_ex(
+            frame.start_pos.to_vector2(),
+            frame.end_pos.to_vector2(),
+            frame.pen_size,
+            frame.color.to_raycolor(),
+        );
+        d.draw_circle_v(
+            frame.end_pos.to_vector2(),
+            frame.pen_size / 2.0,
+            frame.color.to_raycolor(),
+        );
+    }
+}
+
+impl Fill {
+    pub fn vertical_range(&self) -> (f32, f32) {
+        self.points.iter().fold((f32::MAX, f32::MIN), |(top, bottom), p| {
+            (top.min(p.y), bottom.max(p.y))
+        })
+    }
+    //the parts of the horizontal line at y that are inside the shape, from left to right
+    pub fn spans(&self, y: f32) -> Vec<(f32, f32)> {
+        //where each edge crosses the line, and whether it was going down or up
+        let mut crossings: Vec<(f32, i32)> = Vec::new();
+        for (i, a) in self.points.iter().enumerate() {
+            let b = &self.points[(i + 1) % self.points.len()]; //the last edge closes the shape
+            if (a.y <= y) != (b.y <= y) {
+                let x = a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x);
+                crossings.push((x, if b.y > a.y { 1 } else { -1 }));
+            }
+        }
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut spans: Vec<(f32, f32)> = Vec::new();
+        let mut winding = 0;
+        for pair in crossings.windows(2) {
+            winding += pair[0].1;
+            let inside = match self.rule {
+                FillRule::NonZero => winding != 0,
+                FillRule::EvenOdd => winding % 2 != 0,
             };
+            if inside && pair[0].0 < pair[1].0 {
+                match spans.last_mut() {
+                    Some(last) if last.1 == pair[0].0 => last.1 = pair[1].0,
+                    _ => spans.push((pair[0].0, pair[1].0)),
+                }
+            }
+        }
+        spans
+    }
+}
+
+//how much of the history the viewer has drawn so far, so students can watch the turtle move
+#[derive(Clone, Debug, PartialEq)]
+pub struct Playback {
+    position: f32, //in segments, the fraction is how far along the next segment is
+    speed: f32,    //segments per second
+    paused: bool,
+}
+
+impl Playback {
+    //starts from an empty window
+    pub fn new(speed: f32) -> Self {
+        Playback {
+            position: 0.0,
+            speed,
+            paused: false,
         }
     }
+    //starts with everything drawn, but can still be restarted
+    pub fn finished(speed: f32) -> Self {
+        Playback {
+            position: f32::INFINITY,
+            speed,
+            paused: false,
+        }
+    }
+    pub fn advance(&mut self, seconds: f32, total: usize) {
+        if !self.paused {
+            self.position = (self.position + seconds * self.speed).min(total as f32);
+        }
+    }
+    //stepping pauses, so the step isnt immediately followed by more drawing
+    pub fn step(&mut self, by: f32, total: usize) {
+        self.paused = true;
+        self.position = (self.position.min(total as f32).floor() + by).clamp(0.0, total as f32);
+    }
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+    pub fn change_speed(&mut self, factor: f32) {
+        self.speed = (self.speed * factor).max(1.0);
+    }
+    pub fn restart(&mut self) {
+        self.position = 0.0;
+        self.paused = false;
+    }
+    pub fn finish(&mut self, total: usize) {
+        self.position = total as f32;
+    }
+    //the number of whole segments to draw and how much of the next one
+    pub fn shown(&self, total: usize) -> (usize, f32) {
+        let position = self.position.min(total as f32);
+        (position.floor() as usize, position.fract())
+    }
+    pub fn status(&self, total: usize) -> String {
+        format!(
+            "{}/{} segments  {}/s{}",
+            self.shown(total).0,
+            total,
+            self.speed,
+            if self.paused { "  paused" } else { "" }
+        )
+    }
+}
+
+fn dump_number(n: f32) -> String {
+    let rounded = format!("{:.2}", n);
+    match rounded.trim_end_matches('0').trim_end_matches('.') {
+        "-0" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+fn dump_point(point: &Point) -> String {
+    format!("({}, {})", dump_number(point.x), dump_number(point.y))
 }
 
 #[test]
@@ -189,6 +497,112 @@ fn deg_to_rad_test() {
     //assert!(false); //uncomment to see output
 }
 
+#[test]
+fn summary_test() {
+    let mut t = Turtle::new();
+    assert_eq!(t.summary(), "segments: 0\nbounding box: none\n");
+    t.forward(100.0);
+    t.push();
+    t.turn(90.0);
+    t.pen_up();
+    t.push();
+    t.pen_down();
+    t.push();
+    assert_eq!(
+        t.summary(),
+        "segments: 2\nbounding box: (400, 225) to (500, 425)\n"
+    );
+}
+
+#[test]
+fn dump_test() {
+    let mut t = Turtle::new();
+    assert_eq!(t.dump(), "");
+    t.forward(100.0);
+    t.push();
+    t.turn(90.0);
+    t.pen_up();
+    t.forward(0.004);
+    t.push();
+    t.pen_down();
+    t.set_pen_size(2.5);
+    t.forward(-1.0 / 3.0);
+    t.push();
+    assert_eq!(
+        t.dump(),
+        "(400, 225) to (500, 225) color 255 255 255 size 1\n(500, 225) to (500, 224.67) color 255 255 255 size 2.5\n"
+    );
+}
+
+#[test]
+fn playback_test() {
+    let mut p = Playback::new(10.0);
+    p.advance(0.25, 5);
+    assert_eq!(p.shown(5), (2, 0.5));
+    p.toggle_pause();
+    p.advance(1.0, 5);
+    assert_eq!(p.shown(5), (2, 0.5));
+    p.step(1.0, 5);
+    assert_eq!(p.shown(5), (3, 0.0));
+    p.step(-1.0, 5);
+    assert_eq!(p.shown(5), (2, 0.0));
+    p.toggle_pause();
+    p.advance(100.0, 5);
+    assert_eq!(p.shown(5), (5, 0.0));
+    p.restart();
+    assert_eq!(p.shown(5), (0, 0.0));
+    assert_eq!(Playback::finished(10.0).shown(5), (5, 0.0));
+}
+
+#[test]
+fn jump_test() {
+    let mut t = Turtle::new();
+    assert_eq!(t.position(), Point::new(400.0, 225.0));
+    t.turn(450.0);
+    assert_eq!(t.heading(), 90.0);
+    t.pen_up();
+    t.jump(Point::new(10.0, 20.0));
+    t.pen_down();
+    t.jump(Point::new(10.0, 30.0));
+    assert_eq!(t.position(), Point::new(10.0, 30.0));
+    assert_eq!(t.history()[0].pen_size, 0.0);
+    assert_eq!(t.history()[1].start_pos, Point::new(10.0, 20.0));
+    assert_eq!(t.history()[1].pen_size, 1.0);
+    t.set_turn_mode(TurnModes::RADIAN);
+    t.set_heading(THE_NUMBER_OF_RADIANS_IN_A_CIRCLE / 2.0);
+    t.set_turn_mode(TurnModes::DEGREE);
+    assert_eq!(t.heading(), 180.0);
+}
+
+#[test]
+fn fill_test() {
+    //a five pointed star drawn in one go crosses itself, so the middle is wound twice
+    let mut t = Turtle::new();
+    t.begin_fill();
+    for _ in 0..5 {
+        t.forward(100.0);
+        t.push();
+        t.turn(144.0);
+    }
+    t.end_fill(FillRule::EvenOdd);
+    let fill = t.history().last().unwrap().fill.clone().unwrap();
+    assert_eq!(fill.points.len(), 6);
+    let middle = fill.points.iter().map(|p| p.y).sum::<f32>() / 6.0;
+    assert_eq!(fill.spans(middle).len(), 2); //the two points on either side, with a hole between
+    let nonzero = Fill {
+        rule: FillRule::NonZero,
+        ..fill
+    };
+    assert_eq!(nonzero.spans(middle).len(), 1);
+    assert_eq!(
+        t.summary(),
+        "segments: 5\nfills: 1\nbounding box: (400, 189) to (500, 284)\n"
+    );
+    //ending a fill that never began does nothing
+    t.end_fill(FillRule::NonZero);
+    assert_eq!(t.history().len(), 6);
+}
+
 #[test]
 fn polar_to_rect_test() {
     assert_eq!(Point::new(0.0, 0.0), Turtle::polar_to_rect(0.0, 0.0));